@@ -5,7 +5,6 @@ use playing::Playing;
 use ready::Ready;
 use serde::Serialize;
 use serde_json::from_str;
-use web_sys;
 use yew::prelude::*;
 use yew::services::websocket::{WebSocketService, WebSocketStatus, WebSocketTask};
 use yew::services::ConsoleService;
@@ -22,7 +21,7 @@ pub enum State {
     ReadyToGetTable,
     AtTable(command::TableState),
     ReadyToPlay(Player),
-    Playing(Player, game::Game),
+    Playing(Player, Box<game::Game>),
 }
 
 pub struct GameComponent {
@@ -32,7 +31,7 @@ pub struct GameComponent {
     link: ComponentLink<GameComponent>,
 
     state: State,
-    last_error: Option<String>,
+    last_error: Option<game::error::GameError>,
     props: Props,
 }
 
@@ -136,9 +135,9 @@ impl Component for GameComponent {
             ws: None,
             wss: WebSocketService::new(),
             console: ConsoleService::new(),
-            link: link,
+            link,
 
-            props: props,
+            props,
             state: State::Initial,
             last_error: None,
         }
@@ -174,7 +173,7 @@ impl Component for GameComponent {
                 true
             }
             Msg::ConnectCommand(table) => {
-                let cbout = self.link.callback(|s| Msg::Received(s));
+                let cbout = self.link.callback(Msg::Received);
                 let cbnot = self.link.callback(|input: WebSocketStatus| match input {
                     WebSocketStatus::Closed | WebSocketStatus::Error => Msg::Disconnected,
                     WebSocketStatus::Opened => Msg::Connected,
@@ -235,14 +234,14 @@ impl Component for GameComponent {
             State::Playing(player, game) => {
                 let last_error = if let Some(e) = &self.last_error {
                     html! {
-                        <div class="error"> { &e } </div>
+                        <div class="error"> { e.to_string() } </div>
                     }
                 } else {
                     html! { "" }
                 };
                 html! {
                     <div>
-                        <Playing game=game player=player ondo=self.link.callback(|d| Msg::Do(d)) />
+                        <Playing game=&**game player=player ondo=self.link.callback(|d| Msg::Do(d)) />
                         { last_error }
                     </div>
                 }
@@ -60,20 +60,19 @@ impl Component for BidInput {
         match msg {
             Msg::SetBid(b) => {
                 self.data.bid = b
-                    .filter(|b| self.props.min_amount.map_or(true, |min| b >= &min))
-                    .filter(|b| self.props.max_amount.map_or(true, |max| b <= &max))
+                    .filter(|b| self.props.min_amount.is_none_or(|min| b >= &min))
+                    .filter(|b| self.props.max_amount.is_none_or(|max| b <= &max))
                     .filter(|b| {
-                        self.props.increment.map_or(true, |increment| {
+                        self.props.increment.is_none_or(|increment| {
                             (b - self.props.min_amount.unwrap_or(0)) % increment == 0
                         })
                     });
                 true
             }
             Msg::SubmitBid => {
-                self.data
-                    .bid
-                    .as_ref()
-                    .map(|v| self.props.onsubmit.emit(Some(v.clone())));
+                if let Some(v) = self.data.bid {
+                    self.props.onsubmit.emit(Some(v));
+                }
                 false
             }
             Msg::Pass => {
@@ -51,7 +51,7 @@ impl Component for Card {
         html! {
             <div class=class
                 onclick=self.link.callback(move |e| Msg::Choose)>
-            { self.props.card.to_string() }
+            { self.props.card.glyphs() }
             </div>
         }
     }
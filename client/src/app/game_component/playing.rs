@@ -1,11 +1,10 @@
 use pinochle_lib::{
     command::PlayingInput,
-    game::{self, Game, Input},
-    Card, Player, Suit,
+    game::{self, meld::Meld, states::Bid, Game, Input},
+    Card, Layout, Player, Suit, Team,
 };
 use std::convert::TryInto;
 use std::string::ToString;
-use strum::IntoEnumIterator;
 use yew::callback::Callback;
 use yew::html::{Component, ComponentLink, Html, ShouldRender};
 use yew::macros::{html, Properties};
@@ -56,34 +55,167 @@ impl Component for Playing {
             None
         };
 
+        let layout = self.props.game.rules().layout;
+        let player = self.props.player;
+
         let play_area = if let Game::Playing(game) = &self.props.game {
+            let taken: Vec<String> = teams_from(layout, player)
+                .map(|team| {
+                    format!(
+                        "{} {}",
+                        team_name(layout, team, player),
+                        game.taken(team).len()
+                    )
+                })
+                .collect();
+            let last_trick = match game.last_trick() {
+                Some(trick) => html! {
+                    <div>
+                        { format!("Last trick, led by {} and won by {}: ", trick.leader, trick.winner) }
+                        { for trick.cards.iter().map(|c| html! { <card::Card card=c /> }) }
+                    </div>
+                },
+                None => html! {},
+            };
             html! {
                 <div>
                     <h2>{ "Play area:" }</h2>
-                    <div> { "We've taken " } { game.taken(self.props.player.team()).len() } { " cards. They've taken " } {
-                        game.taken(self.props.player.team().other()).len()
-                    } {" cards."} </div>
+                    <div> { "Cards taken: " } { taken.join(", ") } </div>
                     <div id="play-area">{
                         for game.play_area().iter().map(|c|
                             html! { <card::Card card=c /> })
                     }</div>
+                    { last_trick }
+                </div>
+            }
+        } else {
+            html! {}
+        };
+
+        let stock = match &self.props.game {
+            Game::Stock(game) => Some((
+                game.play_area(),
+                game.trump_card(),
+                game.stock_size(),
+                layout
+                    .players()
+                    .map(|p| (p, game.shown(p)))
+                    .collect::<Vec<_>>(),
+            )),
+            Game::Declaring(game) => Some((
+                &[][..],
+                game.trump_card(),
+                game.stock_size(),
+                layout
+                    .players()
+                    .map(|p| (p, game.shown(p)))
+                    .collect::<Vec<_>>(),
+            )),
+            _ => None,
+        };
+        let stock = if let Some((play_area, trump_card, stock_size, shown)) = stock {
+            let trump_card = trump_card.map_or("none".to_owned(), |c| c.glyphs());
+            html! {
+                <div>
+                    <h2>{ "Play area:" }</h2>
+                    <div> { format!("Trump card: {}, {} cards left to draw", trump_card, stock_size) } </div>
+                    <div id="play-area">{
+                        for play_area.iter().map(|c|
+                            html! { <card::Card card=c /> })
+                    }</div>
+                    { for shown.iter().map(|(p, shown)| html! {
+                        <div>
+                            { format!("{} shows: ", p) }
+                            { for shown.iter().map(|(c, _)| html! { <card::Card card=c /> }) }
+                        </div>
+                    }) }
+                </div>
+            }
+        } else {
+            html! {}
+        };
+
+        let bids = if let Game::Bidding(game) = &self.props.game {
+            let bids: Vec<String> = game
+                .bids()
+                .iter()
+                .map(|(p, bid)| match bid {
+                    Some(Bid::Points(bid)) => format!("{} bid {}", p, bid),
+                    Some(Bid::Moon) => format!("{} bid to shoot the moon", p),
+                    None => format!("{} passed", p),
+                })
+                .collect();
+            let misdeal = match game.misdeal() {
+                Some(misdeal) => html! {
+                    <div>
+                        { format!("{} threw in: ", misdeal.player) }
+                        { for misdeal.hand.iter().map(|c| html! { <card::Card card=c /> }) }
+                    </div>
+                },
+                None => html! {},
+            };
+            html! {
+                <div>
+                    <div> { format!("{} dealt", game.dealer_seat()) } </div>
+                    { misdeal }
+                    <div> { bids.join(", ") } </div>
+                </div>
+            }
+        } else {
+            html! {}
+        };
+
+        let meld: Option<Vec<(Player, &[Meld])>> = match &self.props.game {
+            Game::Melding(game) => Some(layout.players().map(|p| (p, game.meld(p))).collect()),
+            Game::Playing(game) => Some(layout.players().map(|p| (p, game.meld(p))).collect()),
+            Game::Stock(game) => Some(layout.players().map(|p| (p, game.meld(p))).collect()),
+            Game::Declaring(game) => Some(layout.players().map(|p| (p, game.meld(p))).collect()),
+            _ => None,
+        };
+        let meld = if let Some(meld) = meld {
+            html! {
+                <div>
+                    <h2>{ "Meld:" }</h2>
+                    { for meld.iter().map(|(p, meld)| html! {
+                        <div>
+                            { p } { ": " }
+                            { meld.iter().map(|m| m.to_string()).collect::<Vec<_>>().join(", ") }
+                            { " (" } { game::meld::total(meld) } { ")" }
+                        </div>
+                    }) }
                 </div>
             }
         } else {
             html! {}
         };
 
-        let can_play: Vec<_> = Player::iter()
+        let finished = if let Game::Finished(state) = &self.props.game {
+            let result = format!("{} won", team_name(layout, state.winner(), player));
+            let scores: Vec<String> = teams_from(layout, player)
+                .map(|team| format!("{}: {}", team_name(layout, team, player), state.score(team)))
+                .collect();
+            html! {
+                <div>
+                    <h2>{ result }</h2>
+                    <div> { format!("{} after {} rounds", scores.join(", "), state.rounds()) } </div>
+                </div>
+            }
+        } else {
+            html! {}
+        };
+
+        let can_play: Vec<_> = layout
+            .players()
             .filter(|p| self.props.game.can_play(*p))
             .map(|x| x.to_string())
             .collect();
-        let can_play = if can_play.len() == 0 {
+        let can_play = if can_play.is_empty() {
             "no one".to_owned()
         } else {
             let head = &can_play[0..can_play.len() - 1];
             let last = &can_play[can_play.len() - 1];
 
-            if head.len() == 0 {
+            if head.is_empty() {
                 last.to_owned()
             } else {
                 format!("{}, and {}", &head.join(", "), last)
@@ -105,45 +237,124 @@ impl Component for Playing {
                         <HandInput cards=hand />
                     </div>
                 }} else { html!{}}}
+                <input type="button" value="Undo" onclick=self.link.callback(|_| PlayingInput::Undo) />
+                { finished }
+                { bids }
+                { meld }
                 { play_area }
+                { stock }
             </div>
         }
     }
 }
 
+/// Every team, starting with `player`'s
+fn teams_from(layout: Layout, player: Player) -> impl Iterator<Item = Team> {
+    layout
+        .starting_from(player)
+        .take(layout.number_of_teams())
+        .map(move |p| layout.team(p))
+}
+
+fn team_name(layout: Layout, team: Team, player: Player) -> String {
+    if layout.team(player) == team {
+        return "We".to_owned();
+    }
+    let members: Vec<String> = layout
+        .players()
+        .filter(|p| layout.team(*p) == team)
+        .map(|p| p.to_string())
+        .collect();
+    members.join(" and ")
+}
+
+fn round_summary(state: &game::states::FinishedRound, team: Team) -> String {
+    let set = if state.made_the_moon() == Some(team) {
+        ", shot the moon"
+    } else if state.round_score(team) < 0 && state.thrown_in() {
+        ", threw in"
+    } else if state.round_score(team) < 0 {
+        ", went set"
+    } else {
+        ""
+    };
+    format!(
+        "{}: {} meld + {} counters{} = {}. Total: {}",
+        team,
+        state.team_meld(team),
+        state.counters(team),
+        set,
+        state.round_score(team),
+        state.score(team)
+    )
+}
+
 impl Playing {
+    fn throw_in(&self, current_player: Player) -> Html {
+        if self
+            .props
+            .game
+            .legal_inputs(current_player)
+            .contains(&Input::ThrowIn)
+        {
+            html! {
+                <input type="button" value="Throw in" onclick=self.link.callback(|_|
+                    PlayingInput::Play(Input::ThrowIn)) />
+            }
+        } else {
+            html! {}
+        }
+    }
+
     fn already_showing_hand(&self) -> bool {
         match &self.props.game {
             Game::Bidding(_) => false,
             Game::SelectingTrump(_) => false,
             Game::PassingCards(_) => true,
             Game::ReturningCards(_) => true,
+            Game::Discarding(_) => true,
+            Game::Melding(_) => false,
             Game::Playing(_) => true,
+            Game::Stock(_) => true,
+            Game::Declaring(_) => false,
             Game::FinishedRound(_) => true,
-            Game::Finished => true,
+            Game::Finished(_) => true,
         }
     }
 
     fn input(&self, current_player: Player) -> Html {
         match &self.props.game {
             Game::Bidding(s) => {
-                let min_bid = s
-                    .bids()
-                    .iter()
-                    .filter_map(|x| *x)
-                    .map(|x| TryInto::<i32>::try_into(x))
-                    .filter_map(|x| Result::ok(x))
-                    .max()
-                    .map_or(250, |x| x + 25);
+                let min_bid = s.min_bid() as i32;
+                let redeal = if s.check_redeal().is_ok() {
+                    html! {
+                        <input type="button" value="Redeal" onclick=self.link.callback(|_|
+                            PlayingInput::Play(Input::RequestRedeal)) />
+                    }
+                } else {
+                    html! {}
+                };
+                let moon = if s.rules().shoot_the_moon.is_some() {
+                    html! {
+                        <input type="button" value="Shoot the moon" onclick=self.link.callback(|_|
+                            PlayingInput::Play(Input::ShootTheMoon)) />
+                    }
+                } else {
+                    html! {}
+                };
 
                 html! {
-                    <BidInput increment=Some(25)
-                              min_amount=min_bid
-                              onsubmit=self.link.callback(|b: Option<i32>|
-                                PlayingInput::Play(match b {
-                                    Some(b) => Input::Bid(b.try_into().unwrap()),
-                                    None => Input::Pass,
-                                })) />
+                    <div>
+                        <BidInput increment=Some(s.rules().bid_increment as i32)
+                                  min_amount=min_bid
+                                  onsubmit=self.link.callback(|b: Option<i32>|
+                                    PlayingInput::Play(match b {
+                                        Some(b) => Input::Bid(b.try_into().unwrap()),
+                                        None => Input::Pass,
+                                    })) />
+                        { redeal }
+                        { moon }
+                    </div>
                 }
             }
             Game::SelectingTrump(_) => html! {
@@ -166,15 +377,10 @@ impl Playing {
                     .collect();
                 html! {
                     <PassCardsInput
-                        number=4
+                        number=state.rules().pass_count
                         cards=cards
-                        onpass=self.link.callback(|c: Vec<Card>| {
-                            if let [a,b,c,d] = c[..] {
-                                PlayingInput::Play(Input::PassCards(Some([a,b,c,d])))
-                            } else {
-                                panic!("Wrong number of cards passed");
-                            }
-                        }) />
+                        onpass=self.link.callback(|c: Vec<Card>|
+                            PlayingInput::Play(Input::PassCards(Some(c)))) />
                 }
             }
             Game::ReturningCards(state) => {
@@ -185,54 +391,91 @@ impl Playing {
                     .collect();
                 html! {
                     <PassCardsInput
-                        number=4
+                        number=state.rules().pass_count
+                        cards=cards
+                        onpass=self.link.callback(|c: Vec<Card>|
+                            PlayingInput::Play(Input::PassCards(Some(c)))) />
+                }
+            }
+            Game::Discarding(state) => {
+                let cards: Vec<Card> = state
+                    .hand(current_player)
+                    .iter()
+                    .filter_map(|x| *x)
+                    .collect();
+                html! {
+                    <PassCardsInput
+                        number=state.rules().widow
                         cards=cards
-                        onpass=self.link.callback(|c: Vec<Card>| {
-                            if let [a,b,c,d] = c[..] {
-                                PlayingInput::Play(Input::PassCards(Some([a,b,c,d])))
-                            } else {
-                                panic!("Wrong number of cards passed");
-                            }
-                        }) />
+                        onpass=self.link.callback(|c: Vec<Card>|
+                            PlayingInput::Play(Input::PassCards(Some(c)))) />
                 }
             }
+            Game::Melding(_) => html! {
+                <div>
+                    <input type="button" value="Start playing" onclick=self.link.callback(|_|
+                        PlayingInput::Play(Input::Next)) />
+                    { self.throw_in(current_player) }
+                </div>
+            },
             Game::Playing(game) => {
-                let hand = game.hand(current_player);
-                let trump = game.trump();
-                let cards: Vec<(Card, bool)> = hand
+                let legal = self.props.game.legal_inputs(current_player);
+                let cards: Vec<(Card, bool)> = game
+                    .hand(current_player)
                     .iter()
                     .filter_map(|card| *card)
-                    .map(|card| {
-                        (
-                            card,
-                            !game::states::is_legal(game.play_area(), hand, &card, trump).is_ok(),
-                        )
-                    })
+                    .map(|card| (card, !legal.contains(&Input::Play(card))))
+                    .collect();
+                html! {
+                    <div>
+                        <HandInput cards=cards onchoose_card=self.link.callback(|c: Card|
+                            PlayingInput::Play(Input::Play(c))) />
+                        { self.throw_in(current_player) }
+                    </div>
+                }
+            }
+            Game::Stock(game) => {
+                let cards: Vec<(Card, bool)> = game
+                    .hand(current_player)
+                    .iter()
+                    .filter_map(|card| card.map(|card| (card, false)))
                     .collect();
                 html! {
                     <HandInput cards=cards onchoose_card=self.link.callback(|c: Card|
                         PlayingInput::Play(Input::Play(c))) />
                 }
             }
-            Game::FinishedRound(state) => html! {
+            Game::Declaring(game) => html! {
                 <div>
-                    <h2>{ "Us" }</h2>
-                    <div> {
-                        for state.taken(current_player.team()).iter().map(|c|
-                            html! { <card::Card card=c /> })
-                    } </div>
-                    <h2>{ "Them" }</h2>
-                    <div> {
-                        for state.taken(current_player.team().other()).iter().map(|c|
-                            html! { <card::Card card=c /> })
-                    } </div>
-                    <input type="button" value="Next" onclick=self.link.callback(|_|
+                    { for game.declarations().into_iter().map(|meld| html! {
+                        <input type="button" value=meld.to_string() onclick=self.link.callback(move |_|
+                            PlayingInput::Play(Input::Declare(meld))) />
+                    }) }
+                    <input type="button" value="Done" onclick=self.link.callback(|_|
                         PlayingInput::Play(Input::Next)) />
                 </div>
             },
-            Game::Finished => html! {
-                "Finished"
-            },
+            Game::FinishedRound(state) => {
+                let layout = state.layout();
+                html! {
+                    <div>
+                        { for teams_from(layout, current_player).map(|team|
+                            html! { <div> { round_summary(state, team) } </div> }) }
+                        { for teams_from(layout, current_player).map(|team| html! {
+                            <div>
+                                <h2>{ team_name(layout, team, current_player) }</h2>
+                                <div> {
+                                    for state.taken(team).iter().map(|c|
+                                        html! { <card::Card card=c /> })
+                                } </div>
+                            </div>
+                        }) }
+                        <input type="button" value="Next" onclick=self.link.callback(|_|
+                            PlayingInput::Play(Input::Next)) />
+                    </div>
+                }
+            }
+            Game::Finished(_) => html! {},
         }
     }
 }
@@ -7,12 +7,43 @@ use yew::macros::{html, Properties};
 
 use pinochle_lib::{
     command::{TableCommand, TableState},
-    Player,
+    game::rules::RuleSet,
+    Deck, Player,
 };
 
+#[derive(Display, PartialEq, Clone, EnumIter, Debug, Copy)]
+pub enum Variant {
+    #[strum(serialize = "Single deck")]
+    SingleDeck,
+    #[strum(serialize = "Double deck")]
+    DoubleDeck,
+    #[strum(serialize = "Three handed cutthroat")]
+    Cutthroat,
+    #[strum(serialize = "Two handed")]
+    TwoHanded,
+    #[strum(serialize = "Six handed partnership")]
+    SixHanded,
+    #[strum(serialize = "Eight handed partnership")]
+    EightHanded,
+}
+
+impl Variant {
+    fn rules(self) -> RuleSet {
+        match self {
+            Variant::SingleDeck => RuleSet::for_deck(Deck::Single),
+            Variant::DoubleDeck => RuleSet::for_deck(Deck::Double),
+            Variant::Cutthroat => RuleSet::cutthroat(),
+            Variant::TwoHanded => RuleSet::two_handed(),
+            Variant::SixHanded => RuleSet::for_deck(Deck::Triple),
+            Variant::EightHanded => RuleSet::for_deck(Deck::Quadruple),
+        }
+    }
+}
+
 pub enum Msg {
     ToggleReady,
     SetPlayer(Option<Player>),
+    SetVariant(Variant),
 }
 
 #[derive(Display, PartialEq, Clone, EnumIter, Debug, Copy)]
@@ -22,6 +53,10 @@ enum PlayerOption {
     B,
     C,
     D,
+    E,
+    F,
+    G,
+    H,
 }
 
 impl From<Option<Player>> for PlayerOption {
@@ -31,6 +66,10 @@ impl From<Option<Player>> for PlayerOption {
             Some(Player::B) => PlayerOption::B,
             Some(Player::C) => PlayerOption::C,
             Some(Player::D) => PlayerOption::D,
+            Some(Player::E) => PlayerOption::E,
+            Some(Player::F) => PlayerOption::F,
+            Some(Player::G) => PlayerOption::G,
+            Some(Player::H) => PlayerOption::H,
             None => PlayerOption::Unset,
         }
     }
@@ -43,6 +82,10 @@ impl From<PlayerOption> for Option<Player> {
             PlayerOption::B => Some(Player::B),
             PlayerOption::C => Some(Player::C),
             PlayerOption::D => Some(Player::D),
+            PlayerOption::E => Some(Player::E),
+            PlayerOption::F => Some(Player::F),
+            PlayerOption::G => Some(Player::G),
+            PlayerOption::H => Some(Player::H),
             PlayerOption::Unset => None,
         }
     }
@@ -95,22 +138,33 @@ impl Component for Ready {
                     self.props
                         .state
                         .player
-                        .map_or(false, |p| !self.props.state.ready.get_value(p)),
+                        .is_some_and(|p| !self.props.state.ready.get_value(p)),
                 ));
             }
             SetPlayer(player) => {
-                player.map(|player| {
+                if let Some(player) = player {
                     self.props
                         .ontablecommand
                         .emit(TableCommand::SetPlayer(player))
-                });
+                }
+            }
+            SetVariant(variant) => {
+                self.props
+                    .ontablecommand
+                    .emit(TableCommand::SetRules(variant.rules()));
             }
         }
         true
     }
 
     fn view(&self) -> Html {
-        let players: Vec<PlayerOption> = PlayerOption::iter().collect();
+        // the unset option, then one for each seat at the table
+        let players: Vec<PlayerOption> = PlayerOption::iter()
+            .take(self.props.state.ready.iter().count() + 1)
+            .collect();
+        let variants: Vec<Variant> = Variant::iter().collect();
+        // the server sends the table's rules, whoever chose them
+        let variant = Variant::iter().find(|v| v.rules() == self.props.state.rules);
         html! {
             <div>
                 <label for="player">{ " Player: " } </label>
@@ -120,6 +174,13 @@ impl Component for Ready {
                                 onchange=self.link.callback(|e: PlayerOption| Msg::SetPlayer(e.into()))
                                 />
 
+                <label for="variant">{ " Game: " } </label>
+
+                <Select<Variant> options=variants
+                                 selected=variant
+                                 onchange=self.link.callback(Msg::SetVariant)
+                                 />
+
                 <label for="ready">{ " Ready: " } </label>
 
                 {
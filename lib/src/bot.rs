@@ -0,0 +1,49 @@
+//! Computer players, for filling the empty seats at a table
+
+use crate::game::{Game, Input};
+use crate::Player;
+
+pub mod heuristic;
+pub mod ismcts;
+
+pub use heuristic::Heuristic;
+pub use ismcts::{Budget, Ismcts};
+
+/// Decides what a player does next
+pub trait Strategy {
+    /// The input `player` makes, given the game as `player` can see it.
+    /// It's only asked when `player` can play, and answers with one of the
+    /// game's legal inputs.
+    fn choose(&mut self, game: &Game, player: Player) -> Input;
+}
+
+/// Plays one input for the next player, picked by `choose`, and panics if
+/// it's turned down
+#[cfg(test)]
+pub(crate) fn play_input<C>(game: &mut Game, mut choose: C)
+where
+    C: FnMut(&Game, Player) -> Input,
+{
+    let player = game.next_player().expect("the game is over");
+    let input = choose(game, player);
+    if let Err(e) = game.play(player, input.clone()) {
+        panic!("{} was turned down for {}: {}", input, player, e);
+    }
+}
+
+/// Plays inputs picked by `choose` until `done`, or panics after `limit`
+/// inputs
+#[cfg(test)]
+pub(crate) fn play_out<C, D>(game: &mut Game, limit: usize, mut choose: C, done: D)
+where
+    C: FnMut(&Game, Player) -> Input,
+    D: Fn(&Game) -> bool,
+{
+    for _ in 0..limit {
+        if done(game) {
+            return;
+        }
+        play_input(game, &mut choose);
+    }
+    assert!(done(game), "not done after {} inputs", limit);
+}
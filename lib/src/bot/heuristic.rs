@@ -0,0 +1,361 @@
+//! A strategy that plays by rules of thumb without looking ahead: bid on
+//! meld and trump, pass trump and aces to the bidder, lead aces, and give
+//! counters to a partner who's taking the trick
+
+use super::Strategy;
+use crate::game::core::*;
+use crate::game::meld::{self, Meld};
+use crate::game::rules::RuleSet;
+use crate::game::states::{self, count_counters, is_counter, Trick, LAST_TRICK_BONUS};
+use crate::game::{Game, Input};
+use strum::IntoEnumIterator;
+
+/// Plays by rules of thumb. It keeps nothing between inputs, so one can
+/// play every empty seat at a table.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Heuristic;
+
+impl Strategy for Heuristic {
+    fn choose(&mut self, game: &Game, player: Player) -> Input {
+        let hand: Vec<Card> = game
+            .hand(player)
+            .unwrap_or(&[])
+            .iter()
+            .flatten()
+            .copied()
+            .collect();
+        let rules = game.rules();
+
+        match game {
+            Game::Bidding(b) => bid(b, player, &hand),
+            Game::SelectingTrump(_) => {
+                let trump = Suit::iter()
+                    .max_by_key(|trump| hand_value(&hand, *trump, rules))
+                    .unwrap();
+                Input::SelectSuit(trump)
+            }
+            Game::PassingCards(s) => {
+                Input::PassCards(Some(to_partner(&hand, s.trump(), rules.pass_count)))
+            }
+            Game::ReturningCards(s) => {
+                Input::PassCards(Some(give_up(&hand, s.trump(), rules.pass_count)))
+            }
+            Game::Discarding(s) => Input::PassCards(Some(give_up(&hand, s.trump(), rules.widow))),
+            Game::Melding(s) if cant_make_bid(s, player) => Input::ThrowIn,
+            Game::Playing(s) => {
+                let legal: Vec<Card> = hand
+                    .iter()
+                    .copied()
+                    .filter(|c| {
+                        states::is_legal(s.play_area(), s.hand(player), c, s.trump()).is_ok()
+                    })
+                    .collect();
+                let bidding_team = s.winning_bid().is_some_and(|(bidder, _)| {
+                    rules.layout.team(bidder) == rules.layout.team(player)
+                });
+                let table = Table {
+                    layout: rules.layout,
+                    player,
+                    trump: s.trump(),
+                    play_area: s.play_area(),
+                    unseen: unseen(rules.deck, &hand, s.tricks(), s.play_area()),
+                    bidding_team,
+                };
+                Input::Play(table.play(&legal))
+            }
+            Game::Stock(s) => {
+                // any card can be played while there's a stock
+                let table = Table {
+                    layout: rules.layout,
+                    player,
+                    trump: s.trump(),
+                    play_area: s.play_area(),
+                    unseen: unseen(rules.deck, &hand, s.tricks(), s.play_area()),
+                    bidding_team: false,
+                };
+                Input::Play(table.play(&hand))
+            }
+            Game::Declaring(s) => s
+                .declarations()
+                .into_iter()
+                .max_by_key(Meld::value)
+                .map_or(Input::Next, Input::Declare),
+            Game::Melding(_) | Game::FinishedRound(_) | Game::Finished(_) => Input::Next,
+        }
+    }
+}
+
+/// What a hand is likely to make with `trump` as trump: its meld, and a
+/// share of the counters for each trump and ace in it. A partner is
+/// counted on for a quarter of the counters.
+fn hand_value(hand: &[Card], trump: Suit, rules: &RuleSet) -> usize {
+    let meld = meld::total(&meld::find_meld(hand, trump));
+    let winners = hand
+        .iter()
+        .filter(|c| c.suit == trump || c.rank == Rank::Ace)
+        .count();
+    let counters = count_counters(&rules.deck.cards()) + LAST_TRICK_BONUS;
+    let partner = if rules.layout.partner(Player::A).is_some() {
+        counters / 4
+    } else {
+        0
+    };
+    meld + (counters * winners / rules.hand_size()).min(counters) + partner
+}
+
+/// Asks for a redeal when it can, and otherwise bids the minimum for as
+/// long as the hand is worth it. It doesn't bid over its partner.
+fn bid(game: &states::Bidding, player: Player, hand: &[Card]) -> Input {
+    if game.check_redeal().is_ok() {
+        return Input::RequestRedeal;
+    }
+
+    let rules = game.rules();
+    let partner_has_bid = match (game.winning_bid(), rules.layout.partner(player)) {
+        (Some((bidder, _)), Some(partner)) => bidder == partner,
+        _ => false,
+    };
+    let worth = Suit::iter()
+        .map(|trump| hand_value(hand, trump, rules))
+        .max()
+        .unwrap_or(0);
+
+    let min = game.min_bid();
+    if !game.can_pass() || (!partner_has_bid && min <= worth) {
+        Input::Bid(min)
+    } else {
+        Input::Pass
+    }
+}
+
+/// The cards most use to the bidder: trump, best first, and then aces
+fn to_partner(hand: &[Card], trump: Suit, count: usize) -> Vec<Card> {
+    let mut cards = hand.to_vec();
+    cards.sort_by_key(|c| std::cmp::Reverse((c.suit == trump, c.rank == Rank::Ace, c.rank)));
+    cards.truncate(count);
+    cards
+}
+
+/// The cards the bidder can best do without: cards outside meld and
+/// trump, low ones before aces
+fn give_up(hand: &[Card], trump: Suit, count: usize) -> Vec<Card> {
+    let mut meld_cards: Vec<Card> = meld::find_meld(hand, trump)
+        .iter()
+        .flat_map(|m| m.cards(trump))
+        .collect();
+    let mut cards: Vec<(bool, Card)> = hand
+        .iter()
+        .map(|c| match meld_cards.iter().position(|m| m == c) {
+            Some(i) => {
+                meld_cards.swap_remove(i);
+                (true, *c)
+            }
+            None => (false, *c),
+        })
+        .collect();
+    cards.sort_by_key(|(in_meld, c)| (*in_meld, c.suit == trump, c.rank == Rank::Ace, c.rank));
+    cards.into_iter().take(count).map(|(_, c)| c).collect()
+}
+
+/// Whether the bidder's team couldn't make the bid even if it took every
+/// counter
+fn cant_make_bid(game: &states::Melding, player: Player) -> bool {
+    let rules = game.rules();
+    match game.winning_bid() {
+        Some((bidder, bid)) if bidder == player && game.moon_bidder().is_none() => {
+            let most = game.team_meld(rules.layout.team(player))
+                + count_counters(&rules.deck.cards())
+                + LAST_TRICK_BONUS;
+            most < bid
+        }
+        _ => false,
+    }
+}
+
+/// The cards that haven't been played and aren't in `hand`
+fn unseen(deck: Deck, hand: &[Card], tricks: &[Trick], play_area: &[Card]) -> Vec<Card> {
+    let mut unseen = deck.cards();
+    let seen = hand
+        .iter()
+        .chain(tricks.iter().flat_map(|t| t.cards.iter()))
+        .chain(play_area.iter());
+    for card in seen {
+        if let Some(i) = unseen.iter().position(|c| c == card) {
+            unseen.swap_remove(i);
+        }
+    }
+    unseen
+}
+
+/// What a player can see of the trick being played
+struct Table<'a> {
+    layout: Layout,
+    player: Player,
+    trump: Suit,
+    play_area: &'a [Card],
+    unseen: Vec<Card>,
+    /// Whether the player's team won the auction, and so wants trump
+    /// drawn out
+    bidding_team: bool,
+}
+
+impl Table<'_> {
+    fn play(&self, legal: &[Card]) -> Card {
+        if self.play_area.is_empty() {
+            self.lead(legal)
+        } else {
+            self.follow(legal)
+        }
+    }
+
+    /// Whether no card left to play beats `card` in its own suit
+    fn is_top(&self, card: &Card) -> bool {
+        !self
+            .unseen
+            .iter()
+            .any(|c| c.suit == card.suit && c.rank > card.rank)
+    }
+
+    fn wins(&self, card: &Card) -> bool {
+        let mut trick = self.play_area.to_vec();
+        trick.push(*card);
+        states::winning_index(&trick, self.trump) == self.play_area.len()
+    }
+
+    /// Leads an ace, or draws trump with the top trump when the player's
+    /// team is bidding, or leads any other card that can't be beaten in
+    /// its suit. Otherwise it leads low.
+    fn lead(&self, legal: &[Card]) -> Card {
+        let trump = self.trump;
+        let trump_out = self.unseen.iter().any(|c| c.suit == trump);
+
+        legal
+            .iter()
+            .find(|c| c.suit != trump && c.rank == Rank::Ace)
+            .or_else(|| {
+                legal
+                    .iter()
+                    .filter(|_| self.bidding_team && trump_out)
+                    .find(|c| c.suit == trump && self.is_top(c))
+            })
+            .or_else(|| legal.iter().find(|c| c.suit != trump && self.is_top(c)))
+            .copied()
+            .unwrap_or_else(|| lowest(legal, trump))
+    }
+
+    /// Gives counters to a partner whose card can't be beaten in its suit,
+    /// and otherwise takes the trick if it can: cheaply when playing last,
+    /// and with the best card in the suit when someone's still to play.
+    fn follow(&self, legal: &[Card]) -> Card {
+        let layout = self.layout;
+        let trump = self.trump;
+        let played = self.play_area.len();
+        let leader = layout.seat_after(self.player, layout.number_of_players() - played);
+        let winning = states::winning_index(self.play_area, trump);
+        let winner = layout.seat_after(leader, winning);
+        let last = played + 1 == layout.number_of_players();
+
+        if layout.team(winner) == layout.team(self.player) {
+            if last || self.is_top(&self.play_area[winning]) {
+                return *legal
+                    .iter()
+                    .max_by_key(|c| (is_counter(c), c.suit != trump, c.rank != Rank::Ace, c.rank))
+                    .unwrap();
+            }
+            return lowest(legal, trump);
+        }
+
+        let winners: Vec<&Card> = legal.iter().filter(|c| self.wins(c)).collect();
+        let cheapest = if last {
+            winners.iter().min_by_key(|c| (c.suit == trump, c.rank))
+        } else if winners.iter().any(|c| c.suit != trump) {
+            winners
+                .iter()
+                .filter(|c| c.suit != trump)
+                .max_by_key(|c| c.rank)
+        } else {
+            winners.iter().min_by_key(|c| c.rank)
+        };
+        cheapest.map_or_else(|| lowest(legal, trump), |c| **c)
+    }
+}
+
+/// The card that's least use: not a counter or trump, and low
+fn lowest(cards: &[Card], trump: Suit) -> Card {
+    *cards
+        .iter()
+        .min_by_key(|c| (is_counter(c), c.suit == trump, c.rank))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::play_out;
+    use crate::game::dealer::Dealer;
+    use crate::game::notation::parse_cards;
+    use crate::game::states::Project;
+
+    fn choose(game: &Game, player: Player) -> Input {
+        Heuristic.choose(&game.project(player), player)
+    }
+
+    #[test]
+    fn plays_conventionally() -> Result<(), Box<dyn std::error::Error>> {
+        let hands = PlayerMap::new(
+            parse_cards("TH AS 9C")?,
+            parse_cards("9S JC QC")?,
+            parse_cards("KH TC AC")?,
+            parse_cards("JS QS KC")?,
+        );
+        let rules = RuleSet {
+            pass_count: 1,
+            ..RuleSet::default()
+        };
+        let mut game = Game::with_rules(Player::A, hands, rules);
+        for (player, input) in &[
+            (Player::A, "bid 250"),
+            (Player::B, "pass"),
+            (Player::C, "pass"),
+            (Player::D, "pass"),
+            (Player::A, "trump H"),
+        ] {
+            game.play(*player, input.parse()?)?;
+        }
+
+        // the partner passes trump, and the bidder keeps it
+        let card = |s: &str| s.parse::<Card>().unwrap();
+        assert_eq!(
+            choose(&game, Player::C),
+            Input::PassCards(Some(vec![card("KH")]))
+        );
+        game.play(Player::C, choose(&game, Player::C))?;
+        assert_eq!(
+            choose(&game, Player::A),
+            Input::PassCards(Some(vec![card("9C")]))
+        );
+        game.play(Player::A, choose(&game, Player::A))?;
+        game.play(Player::A, choose(&game, Player::A))?;
+
+        // an ace is led, and the partner gives it a ten rather than an ace
+        assert_eq!(choose(&game, Player::A), Input::Play(card("AS")));
+        game.play(Player::A, Input::Play(card("AS")))?;
+        game.play(Player::B, choose(&game, Player::B))?;
+        assert_eq!(choose(&game, Player::C), Input::Play(card("TC")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bots_finish_games() {
+        for rules in &[
+            RuleSet::default(),
+            RuleSet::for_deck(Deck::Double),
+            RuleSet::cutthroat(),
+            RuleSet::two_handed(),
+        ] {
+            let mut game = Game::with_dealer(Player::A, Dealer::Seeded(3), rules.clone());
+            play_out(&mut game, 5000, choose, |game| game.finished().is_some());
+        }
+    }
+}
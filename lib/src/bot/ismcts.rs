@@ -0,0 +1,666 @@
+//! Information set Monte Carlo tree search. Each iteration guesses at the
+//! cards the player can't see, keeping to what they've seen played, and
+//! plays the guess out with the game engine. Every guess shares one tree,
+//! so it learns which inputs do well whatever the hidden hands turn out to
+//! be.
+
+use super::{Heuristic, Strategy};
+use crate::game::core::*;
+use crate::game::states::{self, count_counters, Trick, LAST_TRICK_BONUS};
+use crate::game::{Game, Input};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::time::{Duration, Instant};
+
+/// How strongly the search favours inputs it hasn't played out much over
+/// ones that have done well so far
+const EXPLORATION: f64 = 0.7;
+
+/// How many times to try dealing a guess that fits what's known of every
+/// hand before settling for one that doesn't
+const GUESS_ATTEMPTS: usize = 20;
+
+/// How much searching to do for each input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    /// Plays out this many guesses, so the same seed always plays the same
+    /// way
+    Iterations(usize),
+    /// Plays out as many guesses as it can in the time. This needs a clock,
+    /// so it can't be used on wasm.
+    Time(Duration),
+}
+
+/// How one of the inputs open to a player fared in a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    pub input: Input,
+    /// The number of guesses the input was played out in
+    pub visits: usize,
+    /// The average number of points the player's team finished the round
+    /// ahead of the best other team, counting from the start of the search
+    pub margin: f64,
+}
+
+/// Searches over guesses at the hidden hands, playing each one out with
+/// `Heuristic` players. It remembers the cards it passes and discards for
+/// the rest of the deal, so it should see every input of the seats it
+/// plays.
+#[derive(Debug, Clone)]
+pub struct Ismcts {
+    budget: Budget,
+    rng: ChaCha8Rng,
+    given: Vec<Given>,
+}
+
+/// Cards `from` passed to `to`, or discarded into the widow when `to` is
+/// `None`
+#[derive(Debug, Clone)]
+struct Given {
+    deal: usize,
+    from: Player,
+    to: Option<Player>,
+    cards: Vec<Card>,
+}
+
+impl Ismcts {
+    pub fn new(budget: Budget) -> Ismcts {
+        Ismcts::seeded(budget, rand::random())
+    }
+
+    /// A search that always guesses the same way, for reproducing games
+    pub fn seeded(budget: Budget, seed: u64) -> Ismcts {
+        Ismcts {
+            budget,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            given: Vec::new(),
+        }
+    }
+
+    /// Searches from `game` as `player` sees it and reports on each input
+    /// open to them, the most played out first. The first is the one
+    /// `choose` makes. When there's only one input it isn't searched.
+    pub fn evaluate(&mut self, game: &Game, player: Player) -> Vec<Evaluation> {
+        let inputs = inputs(game, player);
+        if inputs.len() <= 1 {
+            return inputs
+                .into_iter()
+                .map(|input| Evaluation {
+                    input,
+                    visits: 0,
+                    margin: 0.0,
+                })
+                .collect();
+        }
+
+        self.given.retain(|g| Some(g.deal) == game.deals());
+        let knowledge = Knowledge::new(game, player, &self.given);
+        let mut tree = Tree::new(game, player);
+        let deadline = match self.budget {
+            Budget::Time(time) => Some(Instant::now() + time),
+            Budget::Iterations(_) => None,
+        };
+
+        let mut iterations = 0;
+        loop {
+            let done = match (self.budget, deadline) {
+                (Budget::Iterations(n), _) => iterations >= n,
+                (_, Some(deadline)) => iterations > 0 && Instant::now() >= deadline,
+                _ => true,
+            };
+            if done {
+                break;
+            }
+
+            let mut guess = game.clone();
+            let (hands, widow) = knowledge.guess(&mut self.rng);
+            guess.reveal(&hands, &widow);
+            tree.iterate(guess, &mut self.rng);
+            iterations += 1;
+        }
+        tree.evaluations()
+    }
+
+    fn remember(&mut self, game: &Game, player: Player, input: &Input) {
+        let to = match game {
+            Game::PassingCards(_) | Game::ReturningCards(_) => game.rules().layout.partner(player),
+            Game::Discarding(_) => None,
+            _ => return,
+        };
+        if let (Some(deal), Input::PassCards(Some(cards))) = (game.deals(), input) {
+            self.given.push(Given {
+                deal,
+                from: player,
+                to,
+                cards: cards.clone(),
+            });
+        }
+    }
+}
+
+impl Strategy for Ismcts {
+    fn choose(&mut self, game: &Game, player: Player) -> Input {
+        let input = self
+            .evaluate(game, player)
+            .into_iter()
+            .next()
+            .expect("a player who can play has an input to make")
+            .input;
+        self.remember(game, player, &input);
+        input
+    }
+}
+
+/// The inputs searched for `player`. Cards to pass are left to the
+/// heuristic, as there are too many ways to choose them. Only the smallest
+/// bid is tried, since a higher one can still be made on a later turn.
+fn inputs(game: &Game, player: Player) -> Vec<Input> {
+    match game {
+        Game::PassingCards(_) | Game::ReturningCards(_) | Game::Discarding(_) => {
+            vec![Heuristic.choose(game, player)]
+        }
+        Game::Bidding(b) => game
+            .legal_inputs(player)
+            .into_iter()
+            .filter(|input| match input {
+                Input::Bid(amount) => *amount == b.min_bid(),
+                _ => true,
+            })
+            .collect(),
+        _ => game.legal_inputs(player),
+    }
+}
+
+fn remove_card(cards: &mut Vec<Card>, card: &Card) -> bool {
+    match cards.iter().position(|c| c == card) {
+        Some(i) => {
+            cards.swap_remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// What a player knows of the cards they can't see
+#[derive(Debug)]
+struct Knowledge {
+    layout: Layout,
+    /// Unseen cards that could be anywhere
+    free: Vec<Card>,
+    /// The number of unknown cards in each hand
+    slots: PlayerMap<usize>,
+    widow_slots: usize,
+    /// Unknown cards that are known to be in each hand, from meld, cards
+    /// on the table or cards the player gave them
+    held: PlayerMap<Vec<Card>>,
+    widow_held: Vec<Card>,
+    /// Suits each player has none of above a rank, or none of at all, from
+    /// the cards they were made to play
+    limits: PlayerMap<Vec<(Suit, Option<Rank>)>>,
+}
+
+impl Knowledge {
+    fn new(game: &Game, player: Player, given: &[Given]) -> Knowledge {
+        let rules = game.rules();
+        let layout = rules.layout;
+
+        let mut free = rules.deck.cards();
+        for card in game.cards().iter().flatten() {
+            remove_card(&mut free, card);
+        }
+
+        let unknown = |cards: &[Option<Card>]| cards.iter().filter(|c| c.is_none()).count();
+        let slots = PlayerMap::from_fn(layout, |p| unknown(game.hand(p).unwrap_or(&[])));
+        let widow_slots = unknown(game.widow().unwrap_or(&[]));
+
+        let (tricks, play_area, trump): (&[Trick], &[Card], Option<Suit>) = match game {
+            Game::Playing(b) => (b.tricks(), b.play_area(), Some(b.trump())),
+            Game::Stock(b) => (b.tricks(), b.play_area(), Some(b.trump())),
+            Game::Melding(b) => (&[], &[], Some(b.trump())),
+            _ => (&[], &[], None),
+        };
+        let played = |p: Player| -> Vec<Card> {
+            let leader = layout.seat_after(player, layout.number_of_players() - play_area.len());
+            tricks
+                .iter()
+                .flat_map(|t| t.card(p, layout))
+                .chain((0..play_area.len()).filter_map(|i| {
+                    if layout.seat_after(leader, i) == p {
+                        Some(play_area[i])
+                    } else {
+                        None
+                    }
+                }))
+                .collect()
+        };
+
+        let mut held = PlayerMap::from_fn(layout, |p| {
+            let mut known: Vec<Card> = Vec::new();
+            let mut add = |cards: Vec<Card>| {
+                for card in cards.iter() {
+                    let more = cards.iter().filter(|c| *c == card).count();
+                    let have = known.iter().filter(|c| *c == card).count();
+                    if more > have {
+                        known.push(*card);
+                    }
+                }
+            };
+            let meld = match game {
+                Game::Melding(b) => b.meld(p),
+                Game::Playing(b) => b.meld(p),
+                _ => &[],
+            };
+            if let Some(trump) = trump {
+                for m in meld {
+                    add(m.cards(trump));
+                }
+            }
+            let shown = match game {
+                Game::Stock(b) => b.shown(p),
+                Game::Declaring(b) => b.shown(p),
+                _ => &[],
+            };
+            add(shown.iter().map(|(c, _)| *c).collect());
+            for g in given.iter().filter(|g| g.from == player && g.to == Some(p)) {
+                add(g.cards.clone());
+            }
+            for card in played(p) {
+                remove_card(&mut known, &card);
+            }
+            known
+        });
+
+        let mut widow_held: Vec<Card> = given
+            .iter()
+            .filter(|g| g.from == player && g.to.is_none())
+            .flat_map(|g| g.cards.iter().copied())
+            .collect();
+
+        // only cards that are still unseen can be placed, and no more than
+        // there's room for
+        let mut take = |cards: &mut Vec<Card>, room: usize| {
+            let wanted = std::mem::take(cards);
+            for card in wanted {
+                if cards.len() < room && remove_card(&mut free, &card) {
+                    cards.push(card);
+                }
+            }
+        };
+        for p in layout.players() {
+            take(held.get_value_mut(p), *slots.get_value(p));
+        }
+        take(&mut widow_held, widow_slots);
+
+        // any card can be played while there's a stock
+        let mut limits = PlayerMap::from_fn(layout, |_| Vec::new());
+        if let (false, Some(trump)) = (rules.stock, trump) {
+            let leader = layout.seat_after(player, layout.number_of_players() - play_area.len());
+            let plays = tricks
+                .iter()
+                .map(|t| (t.leader, t.cards.as_slice()))
+                .chain(Some((leader, play_area)));
+            for (leader, cards) in plays {
+                for i in 1..cards.len() {
+                    let limits = limits.get_value_mut(layout.seat_after(leader, i));
+                    add_limits(limits, &cards[..i], &cards[i], trump);
+                }
+            }
+        }
+
+        Knowledge {
+            layout,
+            free,
+            slots,
+            widow_slots,
+            held,
+            widow_held,
+            limits,
+        }
+    }
+
+    fn can_hold(&self, p: Player, card: &Card) -> bool {
+        !self
+            .limits
+            .get_value(p)
+            .iter()
+            .any(|(suit, rank)| card.suit == *suit && rank.is_none_or(|r| card.rank > r))
+    }
+
+    /// Deals the unseen cards into the unknown slots of each hand and the
+    /// widow, keeping to what's known of each hand if it can
+    fn guess(&self, rng: &mut ChaCha8Rng) -> (PlayerMap<Vec<Card>>, Vec<Card>) {
+        let mut players: Vec<Player> = self.layout.players().collect();
+        players.sort_by_key(|p| std::cmp::Reverse(self.limits.get_value(*p).len()));
+
+        for attempt in 1..=GUESS_ATTEMPTS {
+            let strict = attempt < GUESS_ATTEMPTS;
+            let mut free = self.free.clone();
+            free.as_mut_slice().shuffle(rng);
+
+            let mut hands = self.held.clone();
+            let mut fits = true;
+            for p in players.iter() {
+                let hand = hands.get_value_mut(*p);
+                let room = *self.slots.get_value(*p) - hand.len();
+                let mut dealt = 0;
+                let mut i = 0;
+                while dealt < room && i < free.len() {
+                    if !strict || self.can_hold(*p, &free[i]) {
+                        hand.push(free.swap_remove(i));
+                        dealt += 1;
+                    } else {
+                        i += 1;
+                    }
+                }
+                fits &= dealt == room;
+            }
+
+            if fits || !strict {
+                let mut widow = self.widow_held.clone();
+                let room = self.widow_slots - widow.len();
+                widow.extend(free.into_iter().take(room));
+                return (hands, widow);
+            }
+        }
+        unreachable!("the last attempt always fits")
+    }
+}
+
+/// What playing `card` after `before` shows about the rest of a hand. A
+/// player who didn't follow suit has none of it, and one who didn't trump
+/// either has no trump. A player who followed without beating the winning
+/// card has nothing in its suit that would.
+fn add_limits(limits: &mut Vec<(Suit, Option<Rank>)>, before: &[Card], card: &Card, trump: Suit) {
+    let led = before[0].suit;
+    let winning = before[states::winning_index(before, trump)];
+
+    if card.suit != led {
+        limits.push((led, None));
+        if card.suit != trump {
+            limits.push((trump, None));
+        }
+    }
+    if winning.suit == card.suit && card.rank <= winning.rank {
+        limits.push((card.suit, Some(winning.rank)));
+    }
+}
+
+/// A node for each input tried, under the node for the state it was made
+/// in. The same input can be open in some guesses and not others, so each
+/// node counts how often it was available as well as how often it was
+/// played.
+#[derive(Debug)]
+struct Node {
+    input: Option<Input>,
+    /// The player who made the input
+    player: Player,
+    children: Vec<usize>,
+    visits: usize,
+    available: usize,
+    /// The total reward for the player's team
+    reward: f64,
+}
+
+#[derive(Debug)]
+struct Tree {
+    nodes: Vec<Node>,
+    layout: Layout,
+    deal: Option<usize>,
+    scores: Vec<isize>,
+    /// Points a round is roughly worth, to keep rewards near one
+    scale: f64,
+}
+
+impl Tree {
+    fn new(game: &Game, player: Player) -> Tree {
+        let rules = game.rules();
+        let counters = count_counters(&rules.deck.cards()) + LAST_TRICK_BONUS;
+        Tree {
+            nodes: vec![Node {
+                input: None,
+                player,
+                children: Vec::new(),
+                visits: 0,
+                available: 0,
+                reward: 0.0,
+            }],
+            layout: rules.layout,
+            deal: game.deals(),
+            scores: rules.layout.teams().map(|t| game.score(t)).collect(),
+            scale: counters as f64,
+        }
+    }
+
+    /// Whether the round being searched is over, or was thrown in for a
+    /// redeal
+    fn is_over(&self, game: &Game) -> bool {
+        match game {
+            Game::FinishedRound(_) | Game::Finished(_) => true,
+            _ => game.deals() != self.deal,
+        }
+    }
+
+    fn to_move(&self, game: &Game) -> Player {
+        game.next_player()
+            .expect("someone can play before the round is over")
+    }
+
+    /// Walks down the tree through inputs open in `game`, adds the first
+    /// one that hasn't been tried, and plays the rest of the round out
+    fn iterate(&mut self, mut game: Game, rng: &mut ChaCha8Rng) {
+        let mut path = vec![0];
+        let mut node = 0;
+
+        while !self.is_over(&game) {
+            let player = self.to_move(&game);
+            let open = inputs(&game, player);
+
+            let mut untried: Vec<&Input> = Vec::new();
+            for input in open.iter() {
+                let child = self.nodes[node]
+                    .children
+                    .iter()
+                    .copied()
+                    .find(|c| self.nodes[*c].input.as_ref() == Some(input));
+                match child {
+                    Some(child) => self.nodes[child].available += 1,
+                    None => untried.push(input),
+                }
+            }
+
+            if let Some(input) = untried.choose(rng) {
+                let input = (*input).clone();
+                self.play(&mut game, player, input.clone());
+                let child = self.nodes.len();
+                self.nodes.push(Node {
+                    input: Some(input),
+                    player,
+                    children: Vec::new(),
+                    visits: 0,
+                    available: 1,
+                    reward: 0.0,
+                });
+                self.nodes[node].children.push(child);
+                path.push(child);
+                break;
+            }
+
+            let child = self.select(node, &open);
+            let input = self.nodes[child].input.clone().unwrap();
+            self.play(&mut game, player, input);
+            path.push(child);
+            node = child;
+        }
+
+        while !self.is_over(&game) {
+            let player = self.to_move(&game);
+            let input = Heuristic.choose(&game, player);
+            self.play(&mut game, player, input);
+        }
+
+        let margins = self.margins(&game);
+        for n in path {
+            let node = &mut self.nodes[n];
+            node.visits += 1;
+            node.reward += margins[self.layout.team(node.player) as usize] / self.scale;
+        }
+    }
+
+    fn play(&self, game: &mut Game, player: Player, input: Input) {
+        game.play(player, input)
+            .expect("the tree and the rollouts only play legal inputs");
+    }
+
+    /// The child open in this guess with the best upper confidence bound
+    fn select(&self, node: usize, open: &[Input]) -> usize {
+        let bound = |n: &Node| {
+            n.reward / n.visits as f64
+                + EXPLORATION * ((n.available as f64).ln() / n.visits as f64).sqrt()
+        };
+        self.nodes[node]
+            .children
+            .iter()
+            .copied()
+            .filter(|c| {
+                self.nodes[*c]
+                    .input
+                    .as_ref()
+                    .is_some_and(|i| open.contains(i))
+            })
+            .max_by(|a, b| bound(&self.nodes[*a]).total_cmp(&bound(&self.nodes[*b])))
+            .expect("every open input has been tried")
+    }
+
+    /// How far each team finished ahead of the best of the others, counting
+    /// from the start of the search
+    fn margins(&self, game: &Game) -> Vec<f64> {
+        let gains: Vec<isize> = self
+            .layout
+            .teams()
+            .map(|t| game.score(t) - self.scores[t as usize])
+            .collect();
+        self.layout
+            .teams()
+            .map(|t| {
+                let best_other = self
+                    .layout
+                    .teams()
+                    .filter(|o| *o != t)
+                    .map(|o| gains[o as usize])
+                    .max()
+                    .unwrap_or(0);
+                (gains[t as usize] - best_other) as f64
+            })
+            .collect()
+    }
+
+    fn evaluations(&self) -> Vec<Evaluation> {
+        let mut evaluations: Vec<Evaluation> = self.nodes[0]
+            .children
+            .iter()
+            .map(|c| {
+                let node = &self.nodes[*c];
+                Evaluation {
+                    input: node.input.clone().unwrap(),
+                    visits: node.visits,
+                    margin: node.reward / node.visits as f64 * self.scale,
+                }
+            })
+            .collect();
+        evaluations.sort_by_key(|e| std::cmp::Reverse(e.visits));
+        evaluations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::play_out;
+    use crate::game::dealer::Dealer;
+    use crate::game::notation::parse_cards;
+    use crate::game::rules::RuleSet;
+    use crate::game::states::Project;
+
+    #[test]
+    fn guesses_keep_to_what_was_seen() -> Result<(), Box<dyn std::error::Error>> {
+        let hands = PlayerMap::new(
+            parse_cards("TH AS 9C")?,
+            parse_cards("9S JC QC")?,
+            parse_cards("KH TC AC")?,
+            parse_cards("JS QS KC")?,
+        );
+        let rules = RuleSet {
+            pass_count: 1,
+            ..RuleSet::default()
+        };
+        let mut game = Game::with_rules(Player::A, hands, rules);
+        let mut bot = Ismcts::seeded(Budget::Iterations(10), 1);
+        for input in &["bid 250", "pass", "pass", "pass", "trump H", "cards KH"] {
+            let player = game.next_player().unwrap();
+            game.play(player, input.parse()?)?;
+        }
+        let input = bot.choose(&game.project(Player::A), Player::A);
+        assert_eq!(input, "cards 9C".parse()?);
+        game.play(Player::A, input)?;
+        game.play(Player::A, Input::Next)?;
+        for input in &["play AS", "play 9S", "play TC", "play JS"] {
+            let player = game.next_player().unwrap();
+            game.play(player, input.parse()?)?;
+        }
+
+        // C didn't follow spades or trump, and holds the nine A passed
+        let knowledge = Knowledge::new(&game.project(Player::A), Player::A, &bot.given);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..50 {
+            let (hands, _) = knowledge.guess(&mut rng);
+            let c = hands.get_value(Player::C);
+            assert_eq!(c.len(), 2);
+            assert!(c.contains(&"9C".parse()?));
+            assert!(c
+                .iter()
+                .all(|card| card.suit != Suit::Spade && card.suit != Suit::Heart));
+            assert_eq!(hands.get_value(Player::A).len(), 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn evaluates_every_input() {
+        let game = Game::with_dealer(Player::A, Dealer::Seeded(7), RuleSet::default());
+        let player = game.next_player().unwrap();
+        let mut bot = Ismcts::seeded(Budget::Iterations(100), 7);
+        let evaluations = bot.evaluate(&game.project(player), player);
+
+        let mut searched: Vec<Input> = evaluations.iter().map(|e| e.input.clone()).collect();
+        let mut expected = inputs(&game, player);
+        let key = |i: &Input| format!("{}", i);
+        searched.sort_by_key(key);
+        expected.sort_by_key(key);
+        assert_eq!(searched, expected);
+        assert_eq!(evaluations.iter().map(|e| e.visits).sum::<usize>(), 100);
+        assert!(evaluations.windows(2).all(|w| w[0].visits >= w[1].visits));
+    }
+
+    #[test]
+    fn plays_out_rounds() {
+        for rules in &[
+            RuleSet::default(),
+            RuleSet::cutthroat(),
+            RuleSet::two_handed(),
+        ] {
+            let mut game = Game::with_dealer(Player::A, Dealer::Seeded(5), rules.clone());
+            let mut bot = Ismcts::seeded(Budget::Iterations(20), 5);
+            let choose = |game: &Game, player| {
+                if player == Player::A {
+                    bot.choose(&game.project(player), player)
+                } else {
+                    Heuristic.choose(&game.project(player), player)
+                }
+            };
+            play_out(&mut game, 1000, choose, |game| {
+                game.finished_round().is_some()
+            });
+        }
+    }
+}
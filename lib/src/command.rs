@@ -1,5 +1,7 @@
 use super::game::{
     core::{Player, PlayerMap},
+    error::GameError,
+    rules::RuleSet,
     Game, Input,
 };
 use serde::{Deserialize, Serialize};
@@ -7,15 +9,17 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug)]
 pub enum PlayingResponse {
     Played(Player, Input),
-    State(Game),
+    State(Box<Game>),
     Resigned(Player),
     BackToReady,
-    Error(String),
+    Error(GameError),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum PlayingInput {
     Play(Input),
+    /// Takes back the last input played at the table
+    Undo,
     Resign,
 }
 
@@ -28,19 +32,24 @@ pub enum Command {
 pub enum TableCommand {
     SetPlayer(Player),
     SetReady(bool),
+    /// Starts a new game at the table with the given house rules
+    SetRules(RuleSet),
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct TableState {
     pub ready: PlayerMap<bool>,
     pub player: Option<Player>,
+    /// The house rules the next game at the table will be played with
+    pub rules: RuleSet,
 }
 
 impl TableState {
-    pub fn new(player: Option<Player>) -> TableState {
+    pub fn new(player: Option<Player>, rules: RuleSet) -> TableState {
         TableState {
-            ready: PlayerMap::new(false, false, false, false),
-            player: player,
+            ready: PlayerMap::from_fn(rules.layout, |_| false),
+            player,
+            rules,
         }
     }
 }
@@ -1,8 +1,21 @@
 use self::core::*;
+use self::error::GameError;
 use either::Either;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use std::iter;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumDiscriminants};
 pub use Game::*;
 pub mod core;
+pub mod dealer;
+pub mod error;
+pub mod meld;
+pub mod notation;
+pub mod record;
+pub mod rules;
+#[cfg(test)]
+mod simulation;
 pub mod states;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -11,9 +24,13 @@ pub enum Game {
     SelectingTrump(states::SelectingTrump),
     PassingCards(states::PassingCards),
     ReturningCards(states::ReturningCards),
+    Discarding(states::Discarding),
+    Melding(states::Melding),
     Playing(states::Playing),
+    Stock(states::Stock),
+    Declaring(states::Declaring),
     FinishedRound(states::FinishedRound),
-    Finished,
+    Finished(states::Finished),
 }
 
 impl Game {
@@ -21,25 +38,70 @@ impl Game {
         states::Bidding::new(first_player, states::hands_to_option(hands)).into()
     }
 
-    pub fn play(&mut self, player: Player, input: Input) -> Result<(), String> {
+    pub fn with_rules(
+        first_player: Player,
+        hands: PlayerMap<Vec<Card>>,
+        rules: rules::RuleSet,
+    ) -> Game {
+        states::Bidding::with_rules(first_player, states::hands_to_option(hands), rules).into()
+    }
+
+    /// Starts a game where every hand is dealt by `dealer`, so that it can
+    /// be reproduced from the dealer and the inputs played
+    pub fn with_dealer(
+        first_player: Player,
+        dealer: dealer::Dealer,
+        rules: rules::RuleSet,
+    ) -> Game {
+        if rules.stock {
+            states::Stock::with_dealer(first_player, dealer, rules).into()
+        } else {
+            states::Bidding::with_dealer(first_player, dealer, rules).into()
+        }
+    }
+
+    pub fn play(&mut self, player: Player, input: Input) -> Result<(), GameError> {
         use Input::*;
 
         if !self.can_play(player) {
-            Err("Not your turn".to_owned())?
+            return Err(GameError::NotYourTurn);
         }
 
-        let mut input_state = Game::Finished;
-        std::mem::swap(self, &mut input_state);
+        // leave a placeholder behind while the current state is moved out
+        let input_state = std::mem::replace(self, states::Finished::placeholder().into());
 
         let (next, err) = match (input_state, input) {
             (Bidding(state), Bid(amount)) => next_and_error(state.bid(amount)),
             (Bidding(state), Pass) => next_and_error(state.pass()),
+            (Bidding(state), ShootTheMoon) => next_and_error(state.shoot_the_moon()),
+            (Bidding(state), RequestRedeal) => or_unchanged(state.clone().redeal(), state),
             (SelectingTrump(state), SelectSuit(suit)) => (state.select(suit).into(), Ok(())),
-            (PassingCards(state), PassCards(cards)) => (state.pass(cards)?.into(), Ok(())),
-            (ReturningCards(state), PassCards(cards)) => (state.pass(cards)?.into(), Ok(())),
+            (PassingCards(state), PassCards(cards)) => {
+                or_unchanged(state.clone().pass(cards), state)
+            }
+            (ReturningCards(state), PassCards(cards)) => {
+                or_unchanged(state.clone().pass(cards), state)
+            }
+            (Discarding(state), PassCards(cards)) => {
+                or_unchanged(state.clone().discard(cards), state)
+            }
+            (Melding(state), Next) => (state.next().into(), Ok(())),
+            (Melding(state), ThrowIn) => (state.throw_in().into(), Ok(())),
             (Playing(state), Play(card)) => next_and_error(state.play(card)),
+            (Playing(state), ThrowIn) => or_unchanged(state.clone().throw_in(), state),
+            (Stock(state), Play(card)) => next_and_error(state.play(card)),
+            (Declaring(state), Declare(meld)) => {
+                or_unchanged(state.clone().declare(Some(meld)), state)
+            }
+            (Declaring(state), Next) => or_unchanged(state.clone().declare(None), state),
             (FinishedRound(state), Next) => (state.next().into(), Ok(())),
-            (input_state, _) => (input_state, Err("".to_owned())),
+            (input_state, input) => {
+                let err = GameError::WrongPhase {
+                    expected: input_state.expected_inputs(),
+                    got: InputKind::from(&input),
+                };
+                (input_state, Err(err))
+            }
         };
 
         *self = next;
@@ -52,21 +114,289 @@ impl Game {
             SelectingTrump(b) => Some(b.hand(player)),
             PassingCards(b) => Some(b.hand(player)),
             ReturningCards(b) => Some(b.hand(player)),
+            Discarding(b) => Some(b.hand(player)),
+            Melding(b) => Some(b.hand(player)),
             Playing(b) => Some(b.hand(player)),
+            Stock(b) => Some(b.hand(player)),
+            Declaring(b) => Some(b.hand(player)),
             FinishedRound(b) => Some(b.hand(player)),
-            Finished => None,
+            Finished(_) => None,
+        }
+    }
+
+    pub fn widow(&self) -> Option<&[Option<Card>]> {
+        match self {
+            Bidding(b) => Some(b.widow()),
+            SelectingTrump(b) => Some(b.widow()),
+            PassingCards(b) => Some(b.widow()),
+            ReturningCards(b) => Some(b.widow()),
+            Discarding(b) => Some(b.widow()),
+            Melding(b) => Some(b.widow()),
+            Playing(b) => Some(b.widow()),
+            Stock(b) => Some(b.widow()),
+            Declaring(b) => Some(b.widow()),
+            FinishedRound(b) => Some(b.widow()),
+            Finished(_) => None,
+        }
+    }
+
+    /// Every card of the current deal, wherever it is: in a hand, the widow
+    /// or stock, on the table or in the tricks taken. Cards that aren't
+    /// known in a projection are `None`.
+    pub fn cards(&self) -> Vec<Option<Card>> {
+        let layout = self.rules().layout;
+        let mut cards: Vec<Option<Card>> = layout
+            .players()
+            .flat_map(|p| self.hand(p).unwrap_or(&[]).iter().copied())
+            .chain(self.widow().unwrap_or(&[]).iter().copied())
+            .collect();
+
+        let (play_area, taken, trump_card): (&[Card], Vec<Card>, Option<Card>) = match self {
+            Playing(b) => (
+                b.play_area(),
+                layout.teams().flat_map(|t| b.taken(t)).collect(),
+                None,
+            ),
+            Stock(b) => (
+                b.play_area(),
+                layout.teams().flat_map(|t| b.taken(t)).collect(),
+                b.trump_card(),
+            ),
+            Declaring(b) => (
+                &[],
+                layout.teams().flat_map(|t| b.taken(t)).collect(),
+                b.trump_card(),
+            ),
+            FinishedRound(b) => (&[], layout.teams().flat_map(|t| b.taken(t)).collect(), None),
+            _ => (&[], Vec::new(), None),
+        };
+        cards.extend(
+            play_area
+                .iter()
+                .chain(taken.iter())
+                .chain(trump_card.iter())
+                .map(|c| Some(*c)),
+        );
+        cards
+    }
+
+    /// Fills in the cards that aren't known, as `states::Active::reveal`
+    /// does. A finished game has no cards to fill in.
+    pub fn reveal(&mut self, hands: &PlayerMap<Vec<Card>>, widow: &[Card]) {
+        match self {
+            Bidding(b) => b.reveal(hands, widow),
+            SelectingTrump(b) => b.reveal(hands, widow),
+            PassingCards(b) => b.reveal(hands, widow),
+            ReturningCards(b) => b.reveal(hands, widow),
+            Discarding(b) => b.reveal(hands, widow),
+            Melding(b) => b.reveal(hands, widow),
+            Playing(b) => b.reveal(hands, widow),
+            Stock(b) => b.reveal(hands, widow),
+            Declaring(b) => b.reveal(hands, widow),
+            FinishedRound(b) => b.reveal(hands, widow),
+            Finished(_) => (),
+        }
+    }
+
+    pub fn score(&self, team: Team) -> isize {
+        match self {
+            Bidding(b) => b.score(team),
+            SelectingTrump(b) => b.score(team),
+            PassingCards(b) => b.score(team),
+            ReturningCards(b) => b.score(team),
+            Discarding(b) => b.score(team),
+            Melding(b) => b.score(team),
+            Playing(b) => b.score(team),
+            Stock(b) => b.score(team),
+            Declaring(b) => b.score(team),
+            FinishedRound(b) => b.score(team),
+            Finished(b) => b.score(team),
+        }
+    }
+
+    /// The number of times cards have been dealt in this game
+    pub fn deals(&self) -> Option<usize> {
+        match self {
+            Bidding(b) => Some(b.deals()),
+            SelectingTrump(b) => Some(b.deals()),
+            PassingCards(b) => Some(b.deals()),
+            ReturningCards(b) => Some(b.deals()),
+            Discarding(b) => Some(b.deals()),
+            Melding(b) => Some(b.deals()),
+            Playing(b) => Some(b.deals()),
+            Stock(b) => Some(b.deals()),
+            Declaring(b) => Some(b.deals()),
+            FinishedRound(b) => Some(b.deals()),
+            Finished(_) => None,
+        }
+    }
+
+    /// The seat that dealt the hand being played
+    pub fn dealer_seat(&self) -> Option<Player> {
+        match self {
+            Bidding(b) => Some(b.dealer_seat()),
+            SelectingTrump(b) => Some(b.dealer_seat()),
+            PassingCards(b) => Some(b.dealer_seat()),
+            ReturningCards(b) => Some(b.dealer_seat()),
+            Discarding(b) => Some(b.dealer_seat()),
+            Melding(b) => Some(b.dealer_seat()),
+            Playing(b) => Some(b.dealer_seat()),
+            Stock(b) => Some(b.dealer_seat()),
+            Declaring(b) => Some(b.dealer_seat()),
+            FinishedRound(b) => Some(b.dealer_seat()),
+            Finished(_) => None,
+        }
+    }
+
+    /// The player to the dealer's left, who bids or leads first
+    pub fn initial_bidder(&self) -> Option<Player> {
+        let layout = self.rules().layout;
+        self.dealer_seat().map(|p| layout.next(p))
+    }
+
+    pub fn dealer(&self) -> &dealer::Dealer {
+        match self {
+            Bidding(b) => b.dealer(),
+            SelectingTrump(b) => b.dealer(),
+            PassingCards(b) => b.dealer(),
+            ReturningCards(b) => b.dealer(),
+            Discarding(b) => b.dealer(),
+            Melding(b) => b.dealer(),
+            Playing(b) => b.dealer(),
+            Stock(b) => b.dealer(),
+            Declaring(b) => b.dealer(),
+            FinishedRound(b) => b.dealer(),
+            Finished(b) => b.dealer(),
+        }
+    }
+
+    pub fn rules(&self) -> &rules::RuleSet {
+        match self {
+            Bidding(b) => b.rules(),
+            SelectingTrump(b) => b.rules(),
+            PassingCards(b) => b.rules(),
+            ReturningCards(b) => b.rules(),
+            Discarding(b) => b.rules(),
+            Melding(b) => b.rules(),
+            Playing(b) => b.rules(),
+            Stock(b) => b.rules(),
+            Declaring(b) => b.rules(),
+            FinishedRound(b) => b.rules(),
+            Finished(b) => b.rules(),
         }
     }
 
+    /// The first player, in seat order, who can play. Once a round is over
+    /// anyone can move on, and once the game is over no one can play.
+    pub fn next_player(&self) -> Option<Player> {
+        self.rules().layout.players().find(|p| self.can_play(*p))
+    }
+
     pub fn can_play(&self, player: Player) -> bool {
         match self {
             Bidding(b) => b.turn() == player,
             SelectingTrump(b) => b.turn() == player,
             PassingCards(b) => b.turn() == player,
             ReturningCards(b) => b.turn() == player,
+            Discarding(b) => b.turn() == player,
+            Melding(b) => b.turn() == player,
             Playing(b) => b.turn() == player,
+            Stock(b) => b.turn() == player,
+            Declaring(b) => b.turn() == player,
             FinishedRound(_) => true,
-            Finished => false,
+            Finished(_) => false,
+        }
+    }
+
+    /// The kinds of input the game is waiting for
+    pub fn expected_inputs(&self) -> Vec<InputKind> {
+        use InputKind::*;
+
+        match self {
+            Bidding(b) if b.rules().shoot_the_moon.is_some() => {
+                vec![Bid, Pass, RequestRedeal, ShootTheMoon]
+            }
+            Bidding(_) => vec![Bid, Pass, RequestRedeal],
+            SelectingTrump(_) => vec![SelectSuit],
+            PassingCards(_) | ReturningCards(_) | Discarding(_) => vec![PassCards],
+            Melding(_) => vec![Next, ThrowIn],
+            Playing(_) => vec![Play, ThrowIn],
+            Stock(_) => vec![Play],
+            FinishedRound(_) => vec![Next],
+            Declaring(_) => vec![Declare, Next],
+            Finished(_) => vec![],
+        }
+    }
+
+    /// Every input `player` can make right now: `play` turns down anything
+    /// else. The exception is bids, which are only listed up to
+    /// `Bidding::max_bid`. Higher bids are accepted, but can't be made
+    /// without help. Each different choice of cards to pass is listed once.
+    pub fn legal_inputs(&self, player: Player) -> Vec<Input> {
+        if !self.can_play(player) {
+            return Vec::new();
+        }
+
+        match self {
+            Bidding(b) => {
+                let increment = b.rules().bid_increment;
+                let bids = (b.min_bid()..=b.max_bid())
+                    .step_by(increment)
+                    .map(Input::Bid);
+                let pass = if b.can_pass() {
+                    Some(Input::Pass)
+                } else {
+                    None
+                };
+                let redeal = b.check_redeal().ok().map(|_| Input::RequestRedeal);
+                let moon = b.rules().shoot_the_moon.map(|_| Input::ShootTheMoon);
+                pass.into_iter()
+                    .chain(redeal)
+                    .chain(moon)
+                    .chain(bids)
+                    .collect()
+            }
+            SelectingTrump(_) => Suit::iter().map(Input::SelectSuit).collect(),
+            PassingCards(b) => card_choices(b.hand(player), b.rules().pass_count),
+            ReturningCards(b) => card_choices(b.hand(player), b.rules().pass_count),
+            Discarding(b) => card_choices(b.hand(player), b.rules().widow),
+            Melding(_) => vec![Input::Next, Input::ThrowIn],
+            Playing(b) => b
+                .hand(player)
+                .iter()
+                .flatten()
+                .unique()
+                .filter(|card| {
+                    states::is_legal(b.play_area(), b.hand(player), card, b.trump()).is_ok()
+                })
+                .map(|card| Input::Play(*card))
+                .chain(b.check_throw_in().ok().map(|_| Input::ThrowIn))
+                .collect(),
+            Stock(b) => b
+                .hand(player)
+                .iter()
+                .flatten()
+                .unique()
+                .map(|card| Input::Play(*card))
+                .collect(),
+            Declaring(b) => b
+                .declarations()
+                .into_iter()
+                .map(Input::Declare)
+                .chain(iter::once(Input::Next))
+                .collect(),
+            FinishedRound(_) => vec![Input::Next],
+            Finished(_) => Vec::new(),
+        }
+    }
+
+    /// Every card, for going over a hand once it's done. While the game
+    /// goes on the dealer stays hidden, so later deals can't be worked out.
+    pub fn review(&self) -> Result<Game, GameError> {
+        match self {
+            FinishedRound(b) => Ok(b.review().into()),
+            Finished(b) => Ok(b.clone().into()),
+            _ => Err(GameError::HandNotOver),
         }
     }
 
@@ -82,15 +412,33 @@ impl Game {
             _ => None,
         }
     }
+    pub fn melding(&self) -> Option<&states::Melding> {
+        match self {
+            Melding(x) => Some(x),
+            _ => None,
+        }
+    }
     pub fn playing(&self) -> Option<&states::Playing> {
         match self {
             Playing(x) => Some(x),
             _ => None,
         }
     }
-    pub fn finished(&self) -> Option<&()> {
+    pub fn stock(&self) -> Option<&states::Stock> {
+        match self {
+            Stock(x) => Some(x),
+            _ => None,
+        }
+    }
+    pub fn declaring(&self) -> Option<&states::Declaring> {
+        match self {
+            Declaring(x) => Some(x),
+            _ => None,
+        }
+    }
+    pub fn finished(&self) -> Option<&states::Finished> {
         match self {
-            Finished => Some(&()),
+            Finished(x) => Some(x),
             _ => None,
         }
     }
@@ -103,26 +451,42 @@ impl Game {
 }
 
 impl states::Project for Game {
-    fn project(&self, player: Player) -> Self {
+    fn project_for(&self, viewer: states::Viewer) -> Self {
         match self {
-            Bidding(b) => b.project(player).into(),
-            SelectingTrump(b) => b.project(player).into(),
-            PassingCards(b) => b.project(player).into(),
-            ReturningCards(b) => b.project(player).into(),
-            Playing(b) => b.project(player).into(),
-            FinishedRound(b) => b.project(player).into(),
-            Finished => Finished,
+            Bidding(b) => b.project_for(viewer).into(),
+            SelectingTrump(b) => b.project_for(viewer).into(),
+            PassingCards(b) => b.project_for(viewer).into(),
+            ReturningCards(b) => b.project_for(viewer).into(),
+            Discarding(b) => b.project_for(viewer).into(),
+            Melding(b) => b.project_for(viewer).into(),
+            Playing(b) => b.project_for(viewer).into(),
+            Stock(b) => b.project_for(viewer).into(),
+            Declaring(b) => b.project_for(viewer).into(),
+            FinishedRound(b) => b.project_for(viewer).into(),
+            Finished(b) => b.project_for(viewer).into(),
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, EnumDiscriminants)]
+#[strum_discriminants(name(InputKind), derive(Serialize, Deserialize, Display))]
 pub enum Input {
     Bid(usize),
     Pass,
+    /// Throws in a misdealt hand before bidding on it
+    RequestRedeal,
+    /// A bid to take every trick
+    ShootTheMoon,
     SelectSuit(Suit),
-    PassCards(Option<[Card; 4]>),
+    /// Cards passed to a partner, or discarded by the bidder after taking
+    /// the widow
+    PassCards(Option<Vec<Card>>),
     Play(Card),
+    /// Meld declared by the winner of a trick while there's still a stock
+    Declare(meld::Meld),
+    /// The bidder conceding the hand after seeing the meld, before the
+    /// first card is played
+    ThrowIn,
     Next,
 }
 
@@ -131,25 +495,58 @@ impl Input {
         match self {
             Input::Bid(a) => Input::Bid(*a),
             Input::Pass => Input::Pass,
+            Input::RequestRedeal => Input::RequestRedeal,
+            Input::ShootTheMoon => Input::ShootTheMoon,
             Input::SelectSuit(suit) => Input::SelectSuit(*suit),
             Input::PassCards(_) => Input::PassCards(None),
             Input::Play(card) => Input::Play(*card),
+            Input::Declare(meld) => Input::Declare(*meld),
+            Input::ThrowIn => Input::ThrowIn,
             Input::Next => Input::Next,
         }
     }
 }
 
-fn next_and_error<L, R>(s: Either<(L, Option<&str>), R>) -> (Game, Result<(), String>)
+/// Every different way to pick `count` cards from `hand`. A hand that
+/// isn't known can only pass unknown cards.
+fn card_choices(hand: &[Option<Card>], count: usize) -> Vec<Input> {
+    if hand.iter().any(Option::is_none) {
+        return vec![Input::PassCards(None)];
+    }
+
+    let mut hand: Vec<Card> = hand.iter().flatten().copied().collect();
+    hand.sort_by_key(|c| (c.suit as usize, c.rank));
+    hand.into_iter()
+        .combinations(count)
+        .unique()
+        .map(|cards| Input::PassCards(Some(cards)))
+        .collect()
+}
+
+fn next_and_error<L, R>(s: Either<(L, Option<GameError>), R>) -> (Game, Result<(), GameError>)
 where
     L: Into<Game>,
     R: Into<Game>,
 {
     match s {
-        Either::Left((state, err)) => (state.into(), err.map_or(Ok(()), |e| Err(e.to_owned()))),
+        Either::Left((state, err)) => (state.into(), err.map_or(Ok(()), Err)),
         Either::Right(state) => (state.into(), Ok(())),
     }
 }
 
+/// Moves on to the next state, or stays in `state` if the input was turned
+/// down
+fn or_unchanged<S, N>(next: Result<N, GameError>, state: S) -> (Game, Result<(), GameError>)
+where
+    S: Into<Game>,
+    N: Into<Game>,
+{
+    match next {
+        Ok(next) => (next.into(), Ok(())),
+        Err(err) => (state.into(), Err(err)),
+    }
+}
+
 impl<T, U> From<Either<T, U>> for Game
 where
     T: Into<Game>,
@@ -187,15 +584,39 @@ impl From<states::ReturningCards> for Game {
     }
 }
 
+impl From<states::Discarding> for Game {
+    fn from(val: states::Discarding) -> Self {
+        Discarding(val)
+    }
+}
+
+impl From<states::Melding> for Game {
+    fn from(val: states::Melding) -> Self {
+        Melding(val)
+    }
+}
+
 impl From<states::Playing> for Game {
     fn from(val: states::Playing) -> Self {
         Playing(val)
     }
 }
 
+impl From<states::Stock> for Game {
+    fn from(val: states::Stock) -> Self {
+        Stock(val)
+    }
+}
+
+impl From<states::Declaring> for Game {
+    fn from(val: states::Declaring) -> Self {
+        Declaring(val)
+    }
+}
+
 impl From<states::Finished> for Game {
-    fn from(_: states::Finished) -> Self {
-        Finished
+    fn from(val: states::Finished) -> Self {
+        Finished(val)
     }
 }
 
@@ -213,9 +634,13 @@ mod tests {
         suit: Suit::Heart,
         rank: Rank::Ten,
     };
+    const AS: Card = Card {
+        suit: Suit::Spade,
+        rank: Rank::Ace,
+    };
 
     #[test]
-    fn simple_round() -> Result<(), String> {
+    fn simple_round() -> Result<(), GameError> {
         let hands = PlayerMap::new(
             vec![HX, HX, HX, HX],
             vec![HX, HX, HX, HX],
@@ -228,9 +653,12 @@ mod tests {
         game.play(Player::B, Input::Pass)?;
         game.play(Player::C, Input::Bid(275))?;
         game.play(Player::D, Input::Pass)?;
+        game.play(Player::A, Input::Pass)?;
         game.play(Player::C, Input::SelectSuit(Suit::Heart))?;
-        game.play(Player::A, Input::PassCards(Some([HX, HX, HX, HX])))?;
-        game.play(Player::C, Input::PassCards(Some([HX, HX, HX, HX])))?;
+        game.play(Player::A, Input::PassCards(Some(vec![HX, HX, HX, HX])))?;
+        game.play(Player::C, Input::PassCards(Some(vec![HX, HX, HX, HX])))?;
+        assert_eq!(game.melding().unwrap().meld(Player::C), &[]);
+        game.play(Player::C, Input::Next)?;
         game.play(Player::C, Input::Play(HX))?;
         game.play(Player::D, Input::Play(HX))?;
         game.play(Player::A, Input::Play(HX))?;
@@ -249,13 +677,185 @@ mod tests {
         game.play(Player::B, Input::Play(HX))?;
         let finished_round = game.finished_round().unwrap();
         assert_eq!(
-            finished_round.taken(),
-            [
-                vec![HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX,],
-                vec![],
-            ]
+            finished_round.taken(Team::Red),
+            vec![HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX, HX,]
         );
+        assert_eq!(finished_round.taken(Team::Blue), vec![]);
         game.play(Player::C, Input::Next)?;
         Ok(())
     }
+
+    #[test]
+    fn legal_inputs() -> Result<(), GameError> {
+        let card = |s: &str| s.parse::<Card>().unwrap();
+        let (ah, qs, jd) = (card("AH"), card("QS"), card("JD"));
+        let hands = PlayerMap::new(
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+            vec![ah, HX, qs, jd],
+            vec![HX, HX, HX, HX],
+        );
+
+        let mut game = Game::new(Player::A, hands);
+        assert_eq!(game.legal_inputs(Player::B), vec![]);
+        // four tens have no meld and no aces
+        assert_eq!(
+            game.legal_inputs(Player::A),
+            vec![Input::Pass, Input::RequestRedeal, Input::Bid(250)]
+        );
+        game.play(Player::A, Input::Pass)?;
+        game.play(Player::B, Input::Pass)?;
+        // a pinochle is worth bidding on
+        assert_eq!(game.legal_inputs(Player::C).last(), Some(&Input::Bid(275)));
+        game.play(Player::C, Input::Bid(250))?;
+        game.play(Player::D, Input::Pass)?;
+        assert_eq!(game.legal_inputs(Player::C).len(), 4);
+        game.play(Player::C, Input::SelectSuit(Suit::Heart))?;
+        assert_eq!(
+            game.legal_inputs(Player::A),
+            vec![Input::PassCards(Some(vec![HX, HX, HX, HX]))]
+        );
+        game.play(Player::A, Input::PassCards(Some(vec![HX, HX, HX, HX])))?;
+        // four from five tens and three other cards
+        assert_eq!(game.legal_inputs(Player::C).len(), 8);
+        game.play(Player::C, Input::PassCards(Some(vec![HX, HX, HX, HX])))?;
+        game.play(Player::C, Input::Next)?;
+
+        game.play(Player::C, Input::Play(qs))?;
+        assert_eq!(game.legal_inputs(Player::D), vec![Input::Play(HX)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn legal_inputs_are_what_play_accepts() {
+        use crate::bot::{Heuristic, Strategy};
+
+        let rule_sets = [
+            rules::RuleSet {
+                shoot_the_moon: Some(1000),
+                ..rules::RuleSet::default()
+            },
+            rules::RuleSet::for_deck(Deck::Double),
+            rules::RuleSet::cutthroat(),
+            rules::RuleSet::two_handed(),
+        ];
+        for rules in rule_sets.iter() {
+            let deck = rules.deck.cards();
+            // every kind of input but passing cards, which is listed from
+            // every choice of cards in the hand
+            let mut candidates = vec![
+                Input::Pass,
+                Input::RequestRedeal,
+                Input::ShootTheMoon,
+                Input::ThrowIn,
+                Input::Next,
+            ];
+            candidates.extend(Suit::iter().map(Input::SelectSuit));
+            candidates.extend(deck.iter().unique().map(|c| Input::Play(*c)));
+            for trump in Suit::iter() {
+                let melds = meld::find_meld(&deck, trump);
+                candidates.extend(melds.into_iter().map(Input::Declare));
+            }
+
+            let mut game = Game::with_dealer(Player::A, dealer::Dealer::Seeded(1), rules.clone());
+            for _ in 0..300 {
+                if game.finished().is_some() {
+                    break;
+                }
+                for player in rules.layout.players() {
+                    let legal = game.legal_inputs(player);
+                    let bids = match &game {
+                        Bidding(b) => (0..=b.max_bid() + 100).step_by(5).map(Input::Bid).collect(),
+                        _ => vec![],
+                    };
+                    for input in candidates.iter().chain(bids.iter()) {
+                        let accepted = game.clone().play(player, input.clone()).is_ok();
+                        let capped = match (input, &game) {
+                            (Input::Bid(bid), Bidding(b)) => *bid > b.max_bid(),
+                            _ => false,
+                        };
+                        if accepted && !capped {
+                            assert!(legal.contains(input), "{} for {}", input, player);
+                        }
+                    }
+                }
+
+                crate::bot::play_input(&mut game, |game, player| Heuristic.choose(game, player));
+            }
+        }
+    }
+
+    #[test]
+    fn review_once_the_hand_is_over() -> Result<(), GameError> {
+        use states::Project;
+
+        let hands = PlayerMap::new(
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+            vec![AS, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+        );
+        let mut game = Game::new(Player::A, hands);
+        assert_eq!(game.review(), Err(GameError::HandNotOver));
+
+        game.play(Player::A, Input::Pass)?;
+        game.play(Player::B, Input::Pass)?;
+        game.play(Player::C, Input::Bid(250))?;
+        game.play(Player::D, Input::Pass)?;
+        game.play(Player::C, Input::SelectSuit(Suit::Heart))?;
+        game.play(Player::A, Input::PassCards(Some(vec![HX, HX, HX, HX])))?;
+        game.play(Player::C, Input::PassCards(Some(vec![HX, HX, HX, HX])))?;
+        assert_eq!(game.review(), Err(GameError::HandNotOver));
+        game.play(Player::C, Input::ThrowIn)?;
+
+        // the hand thrown in is only shown to everyone in the review
+        let hand = game.finished_round().unwrap().hand(Player::C).to_vec();
+        assert!(hand.contains(&Some(AS)));
+        let spectator = game.project_for(states::Viewer::Spectator);
+        assert!(spectator.finished_round().unwrap().hand(Player::C)[0].is_none());
+        let review = game.review()?;
+        assert_eq!(review.finished_round().unwrap().hand(Player::C), &hand[..]);
+        assert_eq!(review.dealer(), &dealer::Dealer::Hidden);
+
+        Ok(())
+    }
+
+    #[test]
+    fn turned_down_inputs() -> Result<(), GameError> {
+        let hands = PlayerMap::new(
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+        );
+
+        let mut game = Game::new(Player::A, hands);
+        assert_eq!(
+            game.play(Player::B, Input::Pass),
+            Err(GameError::NotYourTurn)
+        );
+        assert_eq!(
+            game.play(Player::A, Input::Play(HX)),
+            Err(GameError::WrongPhase {
+                expected: vec![InputKind::Bid, InputKind::Pass, InputKind::RequestRedeal],
+                got: InputKind::Play,
+            })
+        );
+        game.play(Player::A, Input::Bid(250))?;
+        game.play(Player::B, Input::Pass)?;
+        game.play(Player::C, Input::Pass)?;
+        game.play(Player::D, Input::Pass)?;
+        game.play(Player::A, Input::SelectSuit(Suit::Heart))?;
+
+        // a bad pass leaves the game as it was
+        let before = game.clone();
+        assert_eq!(
+            game.play(Player::C, Input::PassCards(Some(vec![AS, HX, HX, HX]))),
+            Err(GameError::CardNotInHand(AS))
+        );
+        assert_eq!(game, before);
+
+        Ok(())
+    }
 }
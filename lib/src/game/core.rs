@@ -1,16 +1,11 @@
-use itertools::{chain, iproduct};
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use itertools::iproduct;
 use serde::{Deserialize, Serialize};
+use std::iter;
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 
-pub const NUMBER_OF_TEAMS: usize = 2;
-pub const PLAYERS_PER_TEAM: usize = 2;
-pub const NUMBER_OF_PLAYERS: usize = NUMBER_OF_TEAMS * PLAYERS_PER_TEAM;
-
 #[derive(
-    PartialEq, Eq, Debug, EnumString, EnumIter, Clone, Copy, Deserialize, Serialize, Display,
+    PartialEq, Eq, Hash, Debug, EnumString, EnumIter, Clone, Copy, Deserialize, Serialize, Display,
 )]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
@@ -21,28 +16,41 @@ pub enum Suit {
     Spade,
 }
 
-pub fn shuffle() -> PlayerMap<Vec<Card>> {
-    let mut cards: Vec<Card> = chain(
-        iproduct!(Suit::iter(), Rank::iter()),
-        iproduct!(Suit::iter(), Rank::iter()),
-    )
-    .map(|(s, r)| Card { suit: s, rank: r })
-    .collect();
-
-    let mut rng = thread_rng();
-    cards.as_mut_slice().shuffle(&mut rng);
-    let cards = cards;
-
-    let cards_each: usize = cards.len() / NUMBER_OF_PLAYERS;
-    let mut iter = cards.chunks(cards_each);
-
-    PlayerMap::new(
-        iter.next().unwrap().to_vec(),
-        iter.next().unwrap().to_vec(),
-        iter.next().unwrap().to_vec(),
-        iter.next().unwrap().to_vec(),
-    )
+#[derive(
+    PartialEq, Eq, Debug, EnumString, EnumIter, Clone, Copy, Deserialize, Serialize, Display,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Deck {
+    /// Two copies of every card from nine to ace, 48 cards in all
+    Single,
+    /// Four copies of every card from ten to ace, 80 cards in all
+    Double,
+    /// Three copies of every card from nine to ace, 72 cards in all, for
+    /// six players
+    Triple,
+    /// Four copies of every card from nine to ace, 96 cards in all, for
+    /// eight players
+    Quadruple,
 }
+
+impl Deck {
+    pub fn cards(self) -> Vec<Card> {
+        let (copies, nines) = match self {
+            Deck::Single => (2, true),
+            Deck::Double => (4, false),
+            Deck::Triple => (3, true),
+            Deck::Quadruple => (4, true),
+        };
+
+        iter::repeat_n(iproduct!(Suit::iter(), Rank::iter()), copies)
+            .flatten()
+            .filter(|(_, r)| nines || *r != Rank::Nine)
+            .map(|(s, r)| Card { suit: s, rank: r })
+            .collect()
+    }
+}
+
 impl Suit {
     pub fn to_string(&self) -> &str {
         match self {
@@ -59,6 +67,7 @@ impl Suit {
     PartialOrd,
     Ord,
     Eq,
+    Hash,
     Debug,
     EnumString,
     EnumIter,
@@ -92,7 +101,7 @@ impl Rank {
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Card {
     pub suit: Suit,
@@ -100,40 +109,47 @@ pub struct Card {
 }
 
 impl Card {
-    pub fn to_string(&self) -> String {
+    /// The card as players see it, like `10♥`. The `Display` form is the
+    /// plain text notation instead.
+    pub fn glyphs(&self) -> String {
         format!("{}{}", self.rank.to_string(), self.suit.to_string())
     }
 }
 
+#[test]
+fn deck_sizes() {
+    assert_eq!(Deck::Single.cards().len(), 48);
+    assert_eq!(Deck::Double.cards().len(), 80);
+    assert!(Deck::Double.cards().iter().all(|c| c.rank != Rank::Nine));
+    assert_eq!(Deck::Triple.cards().len(), 72);
+    assert_eq!(Deck::Quadruple.cards().len(), 96);
+}
+
 #[test]
 fn test_ranking() {
     assert!(Rank::Nine < Rank::Ten);
     assert!(Rank::King < Rank::Ten);
 }
 
-impl Iterator for Player {
-    type Item = Player;
-
-    fn next(&mut self) -> Option<Player> {
-        let res = Some(*self);
-        *self = Player::next(*self);
-        res
-    }
-}
-
-#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, EnumString, Display)]
+#[derive(
+    Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, EnumString, Display, EnumIter,
+)]
 pub enum Team {
     Red = 0,
     Blue = 1,
+    Green = 2,
 }
 
 impl Team {
+    /// The opposing team, in a layout with two teams
+    #[deprecated(note = "there's no one opposing team with three teams; use Layout::teams")]
     pub fn other(self) -> Team {
         use Team::*;
 
         match self {
             Red => Blue,
             Blue => Red,
+            Green => panic!("Green only plays when there are three teams"),
         }
     }
 }
@@ -146,46 +162,117 @@ pub enum Player {
     B = 1,
     C = 2,
     D = 3,
+    E = 4,
+    F = 5,
+    G = 6,
+    H = 7,
 }
 
-impl Player {
-    pub fn teammate(self) -> Player {
-        match self {
-            Player::A => Player::C,
-            Player::B => Player::D,
-            Player::C => Player::A,
-            Player::D => Player::B,
-        }
+/// How the players sit at the table. Seats go round in order from `A`, and
+/// are dealt into teams in turn, so teammates sit `players / teams` seats
+/// apart.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    players: usize,
+    teams: usize,
+}
+
+impl Layout {
+    /// Four players in two teams of two
+    pub const PARTNERSHIP: Layout = Layout {
+        players: 4,
+        teams: 2,
+    };
+    /// Two players, each playing for themselves
+    pub const HEADS_UP: Layout = Layout {
+        players: 2,
+        teams: 2,
+    };
+    /// Three players, each playing for themselves
+    pub const CUTTHROAT: Layout = Layout {
+        players: 3,
+        teams: 3,
+    };
+    /// Six players in three teams of two
+    pub const SIX_HANDED: Layout = Layout {
+        players: 6,
+        teams: 3,
+    };
+    /// Eight players in two teams of four
+    pub const EIGHT_HANDED: Layout = Layout {
+        players: 8,
+        teams: 2,
+    };
+
+    pub fn number_of_players(self) -> usize {
+        self.players
     }
 
-    pub fn team(self) -> Team {
-        match self {
-            Player::A => Team::Red,
-            Player::B => Team::Blue,
-            Player::C => Team::Red,
-            Player::D => Team::Blue,
-        }
+    pub fn number_of_teams(self) -> usize {
+        self.teams
     }
 
-    pub fn next(self) -> Player {
-        match self {
-            Player::A => Player::B,
-            Player::B => Player::C,
-            Player::C => Player::D,
-            Player::D => Player::A,
+    pub fn players(self) -> impl Iterator<Item = Player> {
+        Player::iter().take(self.players)
+    }
+
+    pub fn teams(self) -> impl Iterator<Item = Team> {
+        Team::iter().take(self.teams)
+    }
+
+    /// The player `n` seats to the left of `p`
+    pub fn seat_after(self, p: Player, n: usize) -> Player {
+        self.players().nth((p as usize + n) % self.players).unwrap()
+    }
+
+    pub fn next(self, p: Player) -> Player {
+        self.seat_after(p, 1)
+    }
+
+    /// The player `p` sits to the left of
+    pub fn previous(self, p: Player) -> Player {
+        self.seat_after(p, self.players - 1)
+    }
+
+    /// Every player in turn order, starting with `p`
+    pub fn starting_from(self, p: Player) -> impl Iterator<Item = Player> {
+        (0..self.players).map(move |n| self.seat_after(p, n))
+    }
+
+    pub fn team(self, p: Player) -> Team {
+        self.teams().nth(p as usize % self.teams).unwrap()
+    }
+
+    /// The teammate sitting across from `p`, if `p` has one. With more than
+    /// one teammate this is the one `p` passes cards with.
+    pub fn partner(self, p: Player) -> Option<Player> {
+        if self.players > self.teams {
+            Some(self.seat_after(p, self.players / 2))
+        } else {
+            None
         }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PlayerMap<T> {
-    values: [T; NUMBER_OF_PLAYERS],
+    values: Vec<T>,
 }
 
 impl<T> PlayerMap<T> {
     pub fn new(a: T, b: T, c: T, d: T) -> PlayerMap<T> {
         PlayerMap {
-            values: [a, b, c, d],
+            values: vec![a, b, c, d],
+        }
+    }
+
+    /// A map with a value for each player of `layout`
+    pub fn from_fn<F>(layout: Layout, f: F) -> PlayerMap<T>
+    where
+        F: FnMut(Player) -> T,
+    {
+        PlayerMap {
+            values: layout.players().map(f).collect(),
         }
     }
 
@@ -197,46 +284,44 @@ impl<T> PlayerMap<T> {
         &mut self.values[p as usize]
     }
 
-    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (Player, &'a T)> {
-        Player::A.zip(&self.values)
+    /// Whether `p` has a seat in this map
+    pub fn has_player(&self, p: Player) -> bool {
+        (p as usize) < self.values.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Player, &T)> {
+        Player::iter().zip(&self.values)
     }
 
-    pub fn iter_all<'a>(&'a self) -> impl Iterator<Item = (Player, &'a T)> {
-        Player::A.zip(&self.values)
+    pub fn iter_all(&self) -> impl Iterator<Item = (Player, &T)> {
+        Player::iter().zip(&self.values)
     }
 
     pub fn map<F, U>(&self, f: F) -> PlayerMap<U>
     where
         F: Fn(Player, &T) -> U,
     {
-        let [a, b, c, d] = &self.values;
-
-        PlayerMap::new(
-            f(Player::A, a),
-            f(Player::B, b),
-            f(Player::C, c),
-            f(Player::D, d),
-        )
+        PlayerMap {
+            values: self.iter().map(|(p, x)| f(p, x)).collect(),
+        }
     }
 
     pub fn map_move<F, U>(self, mut f: F) -> PlayerMap<U>
     where
         F: FnMut(Player, T) -> U,
     {
-        let [a, b, c, d] = self.values;
-
-        PlayerMap::new(
-            f(Player::A, a),
-            f(Player::B, b),
-            f(Player::C, c),
-            f(Player::D, d),
-        )
+        PlayerMap {
+            values: Player::iter()
+                .zip(self.values)
+                .map(|(p, x)| f(p, x))
+                .collect(),
+        }
     }
 }
 
 impl<T> PlayerMap<Option<T>> {
     pub fn unwrap(self) -> PlayerMap<T> {
-        return self.map_move(|_, x| x.unwrap());
+        self.map_move(|_, x| x.unwrap())
     }
 }
 
@@ -247,26 +332,114 @@ where
     pub fn get_player(&self, value: &T) -> Option<Player> {
         self.values
             .iter()
-            .zip(Player::A)
+            .zip(Player::iter())
             .filter(|(v, _)| v == &value)
             .map(|(_, p)| p)
             .next()
     }
 }
 
+#[test]
+fn partners_are_teammates() {
+    use super::rules::RuleSet;
+
+    let mut checked = 0;
+    for players in 1..=Player::iter().count() {
+        for teams in 1..=Team::iter().count() {
+            let layout = Layout { players, teams };
+            let playable = Deck::iter().any(|deck| {
+                let rules = RuleSet {
+                    layout,
+                    ..RuleSet::for_deck(deck)
+                };
+                rules.validate().is_ok()
+            });
+            if !playable {
+                continue;
+            }
+            checked += 1;
+            for p in layout.players() {
+                if let Some(partner) = layout.partner(p) {
+                    assert_eq!(layout.team(partner), layout.team(p), "{:?}", layout);
+                }
+            }
+        }
+    }
+    assert!(checked >= 5);
+
+    let rules = RuleSet {
+        layout: Layout {
+            players: 6,
+            teams: 2,
+        },
+        ..RuleSet::default()
+    };
+    assert!(rules.validate().is_err());
+}
+
 #[test]
 fn player_iterator() {
-    let p = Player::C;
+    let layout = Layout::PARTNERSHIP;
 
-    for x in p {
-        assert_eq!(x, Player::C);
-        break;
-    }
+    assert_eq!(layout.starting_from(Player::C).next(), Some(Player::C));
+
+    let res: Vec<(usize, Player)> = [1, 2, 3]
+        .iter()
+        .zip(layout.starting_from(Player::C))
+        .map(|(l, r)| (*l, r))
+        .collect();
+
+    assert_eq!(res, vec![(1, Player::C), (2, Player::D), (3, Player::A)]);
 
-    let p = Player::C;
-    let res: Vec<(usize, Player)> = vec![1, 2, 3].iter().zip(p).map(|(l, r)| (*l, r)).collect();
+    // the seats wrap at the size of the table, not at the last player
+    assert_eq!(Layout::CUTTHROAT.seat_after(Player::C, 1), Player::A);
+    assert_eq!(Layout::SIX_HANDED.seat_after(Player::E, 2), Player::A);
+    assert_eq!(Layout::EIGHT_HANDED.seat_after(Player::G, 2), Player::A);
+}
 
-    assert_eq!(p, Player::C);
+#[test]
+#[allow(deprecated)]
+fn other_team() {
+    assert_eq!(Team::Red.other(), Team::Blue);
+    assert_eq!(Team::Blue.other(), Team::Red);
+}
 
-    assert_eq!(res, vec![(1, Player::C), (2, Player::D), (3, Player::A)])
+#[test]
+fn layouts() {
+    let partnership = Layout::PARTNERSHIP;
+    assert_eq!(partnership.team(Player::C), Team::Red);
+    assert_eq!(partnership.team(Player::D), Team::Blue);
+    assert_eq!(partnership.partner(Player::B), Some(Player::D));
+    assert_eq!(partnership.next(Player::D), Player::A);
+
+    let cutthroat = Layout::CUTTHROAT;
+    assert_eq!(
+        cutthroat
+            .players()
+            .map(|p| cutthroat.team(p))
+            .collect::<Vec<_>>(),
+        [Team::Red, Team::Blue, Team::Green]
+    );
+    assert_eq!(cutthroat.partner(Player::A), None);
+    assert_eq!(cutthroat.next(Player::C), Player::A);
+    assert_eq!(
+        cutthroat.starting_from(Player::B).collect::<Vec<_>>(),
+        [Player::B, Player::C, Player::A]
+    );
+
+    let six = Layout::SIX_HANDED;
+    assert_eq!(six.team(Player::E), Team::Blue);
+    assert_eq!(six.partner(Player::B), Some(Player::E));
+    assert_eq!(six.next(Player::F), Player::A);
+
+    let eight = Layout::EIGHT_HANDED;
+    assert_eq!(
+        eight
+            .players()
+            .filter(|p| eight.team(*p) == Team::Red)
+            .collect::<Vec<_>>(),
+        [Player::A, Player::C, Player::E, Player::G]
+    );
+    assert_eq!(eight.partner(Player::C), Some(Player::G));
+    assert_eq!(eight.next(Player::H), Player::A);
 }
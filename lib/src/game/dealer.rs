@@ -0,0 +1,171 @@
+use super::core::*;
+use super::rules::RuleSet;
+use super::states::hands_to_option;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// The cards dealt for one hand
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Deal {
+    pub hands: PlayerMap<Vec<Card>>,
+    pub widow: Vec<Card>,
+}
+
+impl From<PlayerMap<Vec<Card>>> for Deal {
+    fn from(hands: PlayerMap<Vec<Card>>) -> Self {
+        Deal {
+            hands,
+            widow: Vec::new(),
+        }
+    }
+}
+
+/// Where the hands of a game come from. Every deal is worked out from the
+/// dealer and the number of the deal alone, so a game can be reproduced
+/// exactly from its dealer and the inputs that were played.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum Dealer {
+    /// Shuffles with a generator seeded from the seed, on a separate stream
+    /// for each deal
+    Seeded(u64),
+    /// Deals these hands in order, starting over once they run out
+    Scripted(Vec<Deal>),
+    /// Stands in for the dealer in a player's projection of the game, where
+    /// the seed would give away everyone's hands. Deals hands of unknown
+    /// cards.
+    Hidden,
+}
+
+impl Dealer {
+    /// A dealer with a fresh random seed
+    pub fn random() -> Dealer {
+        Dealer::Seeded(rand::random())
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            Dealer::Seeded(seed) => Some(*seed),
+            _ => None,
+        }
+    }
+
+    /// The hands and widow for the `deal`th deal of the game, counting from
+    /// zero. Shuffled cards go out from the left of `dealer_seat`.
+    pub fn deal(
+        &self,
+        deal: usize,
+        dealer_seat: Player,
+        rules: &RuleSet,
+    ) -> (PlayerMap<Vec<Option<Card>>>, Vec<Option<Card>>) {
+        match self {
+            Dealer::Seeded(seed) => {
+                let mut rng = ChaCha8Rng::seed_from_u64(*seed);
+                rng.set_stream(deal as u64);
+
+                let mut cards = rules.deck.cards();
+                cards.as_mut_slice().shuffle(&mut rng);
+                let widow = cards.split_off(cards.len() - rules.widow);
+                let mut chunks = cards.chunks(rules.hand_size());
+                let mut hands = PlayerMap::from_fn(rules.layout, |_| Vec::new());
+                for player in rules.layout.starting_from(rules.layout.next(dealer_seat)) {
+                    *hands.get_value_mut(player) = chunks.next().unwrap().to_vec();
+                }
+
+                (
+                    hands_to_option(hands),
+                    widow.into_iter().map(Some).collect(),
+                )
+            }
+            Dealer::Scripted(deals) => {
+                let deal = deals[deal % deals.len()].clone();
+                (
+                    hands_to_option(deal.hands),
+                    deal.widow.into_iter().map(Some).collect(),
+                )
+            }
+            Dealer::Hidden => (
+                PlayerMap::from_fn(rules.layout, |_| vec![None; rules.hand_size()]),
+                vec![None; rules.widow],
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_deals_are_reproducible() {
+        let dealer = Dealer::Seeded(42);
+        let rules = RuleSet::default();
+        assert_eq!(
+            dealer.deal(0, Player::D, &rules),
+            Dealer::Seeded(42).deal(0, Player::D, &rules)
+        );
+        assert_ne!(
+            dealer.deal(0, Player::D, &rules),
+            dealer.deal(1, Player::D, &rules)
+        );
+        assert_ne!(
+            dealer.deal(0, Player::D, &rules),
+            Dealer::Seeded(43).deal(0, Player::D, &rules)
+        );
+
+        // the same cards go out starting from the next dealer's left
+        let (first, _) = dealer.deal(0, Player::D, &rules);
+        let (moved, _) = dealer.deal(0, Player::A, &rules);
+        for player in rules.layout.players() {
+            assert_eq!(
+                first.get_value(player),
+                moved.get_value(rules.layout.next(player))
+            );
+        }
+
+        for rules in &[
+            RuleSet::default(),
+            RuleSet::for_deck(Deck::Double),
+            RuleSet::cutthroat(),
+            RuleSet::two_handed(),
+            RuleSet::for_deck(Deck::Triple),
+            RuleSet::for_deck(Deck::Quadruple),
+        ] {
+            let (hands, widow) = dealer.deal(3, Player::B, rules);
+            assert_eq!(widow.len(), rules.widow);
+            for (_, hand) in hands.iter() {
+                assert_eq!(hand.len(), rules.hand_size());
+            }
+
+            let mut cards: Vec<Card> = hands
+                .iter()
+                .flat_map(|(_, hand)| hand.iter())
+                .chain(widow.iter())
+                .map(|c| c.unwrap())
+                .collect();
+            let mut expected = rules.deck.cards();
+            cards.sort_by_key(|c| (c.suit as usize, c.rank));
+            expected.sort_by_key(|c| (c.suit as usize, c.rank));
+            assert_eq!(cards, expected);
+        }
+    }
+
+    #[test]
+    fn scripted_deals_repeat() {
+        let card = |suit, rank| Card { suit, rank };
+        let first = PlayerMap::new(
+            vec![card(Suit::Heart, Rank::Ace)],
+            vec![card(Suit::Heart, Rank::Ten)],
+            vec![card(Suit::Heart, Rank::King)],
+            vec![card(Suit::Heart, Rank::Queen)],
+        );
+        let second = first.map(|_, hand| vec![card(Suit::Spade, hand[0].rank)]);
+        let dealer = Dealer::Scripted(vec![first.clone().into(), second.clone().into()]);
+
+        let deal = |n| dealer.deal(n, Player::D, &RuleSet::default()).0;
+        assert_eq!(deal(0), hands_to_option(first.clone()));
+        assert_eq!(deal(1), hands_to_option(second));
+        assert_eq!(deal(2), hands_to_option(first));
+    }
+}
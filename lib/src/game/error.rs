@@ -0,0 +1,105 @@
+use super::core::{Card, Suit};
+use super::meld::Meld;
+use super::InputKind;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Why an input was turned down. Nothing about the game changes when an
+/// input is turned down.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum GameError {
+    NotYourTurn,
+    /// Only players in a seat can play, not spectators
+    NotSeated,
+    /// The message couldn't be read as an input, for the reason given
+    InvalidMessage(String),
+    /// The game is waiting for one of the `expected` kinds of input
+    WrongPhase {
+        expected: Vec<InputKind>,
+        got: InputKind,
+    },
+    /// Bids have to be at least `min`, which goes up as players bid
+    BidTooLow {
+        min: usize,
+    },
+    /// Bids have to go up from the minimum bid by the bid increment
+    BadIncrement,
+    /// The last bidder is stuck with the bid when everyone else has passed
+    MustBid,
+    /// The rules don't allow shooting the moon
+    MoonNotAllowed,
+    /// Redeals can only be asked for before bidding
+    AlreadyBid,
+    /// The hand doesn't meet any of the rules for a redeal
+    NotAMisdeal,
+    MustFollowSuit(Suit),
+    MustTrump,
+    /// A card has to beat this one, the winning card in the trick, if it can
+    MustBeat(Card),
+    CardNotInHand(Card),
+    /// Unknown cards can only be passed from a hand that isn't known
+    CardsNotKnown,
+    WrongNumberOfCards {
+        expected: usize,
+    },
+    /// Only the player who won the auction can throw in
+    NotBidder,
+    /// The hand can only be thrown in before the first card is played
+    TooLateToThrowIn,
+    MeldNotInHand(Meld),
+    /// Only meld found in the hand can be declared
+    NotAMeld(Meld),
+    /// Every card of the meld is already on the table in a meld like it
+    MeldAlreadyShown(Meld),
+    /// The house rules can't be played, for the reason given
+    InvalidRules(String),
+    /// There are only `available` inputs to take back
+    NothingToUndo {
+        available: usize,
+    },
+    /// Only the player who made the last input can take it back
+    NotYourInput,
+    /// Every card can only be shown once the hand is over
+    HandNotOver,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use GameError::*;
+
+        match self {
+            NotYourTurn => write!(f, "Not your turn"),
+            NotSeated => write!(f, "Only seated players can play"),
+            InvalidMessage(reason) => write!(f, "Invalid message: {}", reason),
+            WrongPhase { expected, got } => {
+                let expected: Vec<String> = expected.iter().map(ToString::to_string).collect();
+                write!(f, "Expected {}, not {}", expected.join(" or "), got)
+            }
+            BidTooLow { min } => write!(f, "Bid must be at least {}", min),
+            BadIncrement => write!(f, "Bid is not a multiple of the bid increment"),
+            MustBid => write!(f, "Last bidder must not pass if there's no bid"),
+            MoonNotAllowed => write!(f, "Shooting the moon isn't allowed"),
+            AlreadyBid => write!(f, "Redeals must be asked for before bidding"),
+            NotAMisdeal => write!(f, "Hand doesn't qualify for a redeal"),
+            MustFollowSuit(suit) => write!(f, "Must follow suit ({})", suit.to_string()),
+            MustTrump => write!(f, "Must trump"),
+            MustBeat(card) => write!(f, "Must beat the {}", card),
+            CardNotInHand(card) => write!(f, "{} is not in hand", card),
+            CardsNotKnown => write!(f, "Cards passed must be known"),
+            WrongNumberOfCards { expected } => write!(f, "Must pass {} cards", expected),
+            NotBidder => write!(f, "Only the bidder can throw in"),
+            TooLateToThrowIn => write!(f, "Can't throw in once play has started"),
+            MeldNotInHand(meld) => write!(f, "Cards for {} aren't in hand", meld),
+            NotAMeld(meld) => write!(f, "{} isn't meld that can be declared", meld),
+            MeldAlreadyShown(meld) => {
+                write!(f, "{} must use a card that isn't already melded", meld)
+            }
+            InvalidRules(reason) => write!(f, "Invalid rules: {}", reason),
+            NothingToUndo { available } => write!(f, "Only {} inputs to undo", available),
+            NotYourInput => write!(f, "Only the player who made the last input can undo it"),
+            HandNotOver => write!(f, "The hand isn't over yet"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
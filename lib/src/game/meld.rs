@@ -0,0 +1,246 @@
+use super::core::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::iter;
+use strum::IntoEnumIterator;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Meld {
+    /// Ace, ten, king, queen and jack of trump. `Run(2)` is a double run.
+    Run(usize),
+    /// King and queen of trump that aren't already part of a run.
+    RoyalMarriage,
+    /// King and queen of a suit other than trump.
+    Marriage(Suit),
+    /// Queen of spades and jack of diamonds. `Pinochle(2)` is double pinochle.
+    Pinochle(usize),
+    /// Nine of trump.
+    Dix,
+    /// One card of the given rank in every suit. `Around(Rank::Ace, 2)` is
+    /// double aces around.
+    Around(Rank, usize),
+}
+
+const RUN: [Rank; 5] = [Rank::Ace, Rank::Ten, Rank::King, Rank::Queen, Rank::Jack];
+const AROUNDS: [Rank; 4] = [Rank::Ace, Rank::King, Rank::Queen, Rank::Jack];
+
+impl Meld {
+    /// Triples and quadruples only turn up with a double deck, so one table
+    /// covers both decks.
+    pub fn value(&self) -> usize {
+        match *self {
+            Meld::Run(1) => 150,
+            Meld::Run(n) => 1500 * (n - 1),
+            Meld::RoyalMarriage => 40,
+            Meld::Marriage(_) => 20,
+            Meld::Pinochle(1) => 40,
+            Meld::Pinochle(2) => 300,
+            Meld::Pinochle(3) => 600,
+            // there are at most four of each card
+            Meld::Pinochle(_) => 900,
+            Meld::Dix => 10,
+            Meld::Around(rank, n) => {
+                let single = match rank {
+                    Rank::Ace => 100,
+                    Rank::King => 80,
+                    Rank::Queen => 60,
+                    _ => 40,
+                };
+                if n == 1 {
+                    single
+                } else {
+                    single * 5 * n
+                }
+            }
+        }
+    }
+}
+
+impl Meld {
+    /// The cards that make up this meld when `trump` is trump
+    pub fn cards(&self, trump: Suit) -> Vec<Card> {
+        let card = |suit, rank| Card { suit, rank };
+        let (cards, n): (Vec<Card>, usize) = match *self {
+            Meld::Run(n) => (RUN.iter().map(|r| card(trump, *r)).collect(), n),
+            Meld::RoyalMarriage => (vec![card(trump, Rank::King), card(trump, Rank::Queen)], 1),
+            Meld::Marriage(suit) => (vec![card(suit, Rank::King), card(suit, Rank::Queen)], 1),
+            Meld::Pinochle(n) => (
+                vec![
+                    card(Suit::Spade, Rank::Queen),
+                    card(Suit::Diamond, Rank::Jack),
+                ],
+                n,
+            ),
+            Meld::Dix => (vec![card(trump, Rank::Nine)], 1),
+            Meld::Around(rank, n) => (Suit::iter().map(|s| card(s, rank)).collect(), n),
+        };
+        iter::repeat_n(cards, n).flatten().collect()
+    }
+
+    /// Whether both melds are the same kind of meld, whatever their suit or
+    /// multiple
+    pub fn same_kind(&self, other: &Meld) -> bool {
+        match (self, other) {
+            (Meld::Around(a, _), Meld::Around(b, _)) => a == b,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl fmt::Display for Meld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn times(n: usize) -> &'static str {
+            match n {
+                1 => "",
+                2 => "Double ",
+                3 => "Triple ",
+                _ => "Quadruple ",
+            }
+        }
+
+        match self {
+            Meld::Run(n) => write!(f, "{}Run", times(*n)),
+            Meld::RoyalMarriage => write!(f, "Royal marriage"),
+            Meld::Marriage(suit) => write!(f, "Marriage {}", suit.to_string()),
+            Meld::Pinochle(n) => write!(f, "{}Pinochle", times(*n)),
+            Meld::Dix => write!(f, "Dix"),
+            Meld::Around(rank, n) => write!(f, "{}{}s around", times(*n), rank.to_string()),
+        }
+    }
+}
+
+fn count(hand: &[Card], suit: Suit, rank: Rank) -> usize {
+    hand.iter()
+        .filter(|c| c.suit == suit && c.rank == rank)
+        .count()
+}
+
+/// Finds every meld in `hand` when `trump` is trump. A card may be used
+/// in melds of different kinds (the queen of spades can be part of both a
+/// pinochle and queens around), but not twice in melds of the same kind.
+pub fn find_meld(hand: &[Card], trump: Suit) -> Vec<Meld> {
+    let mut meld = Vec::new();
+
+    let runs = RUN.iter().map(|r| count(hand, trump, *r)).min().unwrap();
+    if runs > 0 {
+        meld.push(Meld::Run(runs));
+    }
+
+    let royal_marriages = count(hand, trump, Rank::King).min(count(hand, trump, Rank::Queen));
+    for _ in runs..royal_marriages {
+        meld.push(Meld::RoyalMarriage);
+    }
+
+    for suit in Suit::iter().filter(|s| *s != trump) {
+        let marriages = count(hand, suit, Rank::King).min(count(hand, suit, Rank::Queen));
+        for _ in 0..marriages {
+            meld.push(Meld::Marriage(suit));
+        }
+    }
+
+    let pinochles =
+        count(hand, Suit::Spade, Rank::Queen).min(count(hand, Suit::Diamond, Rank::Jack));
+    if pinochles > 0 {
+        meld.push(Meld::Pinochle(pinochles));
+    }
+
+    for _ in 0..count(hand, trump, Rank::Nine) {
+        meld.push(Meld::Dix);
+    }
+
+    for rank in AROUNDS.iter() {
+        let arounds = Suit::iter().map(|s| count(hand, s, *rank)).min().unwrap();
+        if arounds > 0 {
+            meld.push(Meld::Around(*rank, arounds));
+        }
+    }
+
+    meld
+}
+
+pub fn total(meld: &[Meld]) -> usize {
+    meld.iter().map(Meld::value).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(suit: Suit, rank: Rank) -> Card {
+        Card { suit, rank }
+    }
+
+    fn run(suit: Suit) -> Vec<Card> {
+        RUN.iter().map(|r| card(suit, *r)).collect()
+    }
+
+    #[test]
+    fn run_absorbs_royal_marriage() {
+        let mut hand = run(Suit::Heart);
+        assert_eq!(find_meld(&hand, Suit::Heart), vec![Meld::Run(1)]);
+
+        hand.push(card(Suit::Heart, Rank::King));
+        hand.push(card(Suit::Heart, Rank::Queen));
+        assert_eq!(
+            find_meld(&hand, Suit::Heart),
+            vec![Meld::Run(1), Meld::RoyalMarriage]
+        );
+        assert_eq!(total(&find_meld(&hand, Suit::Heart)), 190);
+
+        // the same cards are only marriages when hearts aren't trump
+        assert_eq!(
+            find_meld(&hand, Suit::Club),
+            vec![Meld::Marriage(Suit::Heart), Meld::Marriage(Suit::Heart)]
+        );
+    }
+
+    #[test]
+    fn pinochle_and_arounds_share_cards() {
+        let mut hand: Vec<Card> = Suit::iter().map(|s| card(s, Rank::Queen)).collect();
+        hand.push(card(Suit::Diamond, Rank::Jack));
+        hand.push(card(Suit::Spade, Rank::Nine));
+
+        assert_eq!(
+            find_meld(&hand, Suit::Spade),
+            vec![Meld::Pinochle(1), Meld::Dix, Meld::Around(Rank::Queen, 1)]
+        );
+        assert_eq!(total(&find_meld(&hand, Suit::Spade)), 110);
+    }
+
+    #[test]
+    fn doubles() {
+        let mut hand: Vec<Card> = Suit::iter()
+            .flat_map(|s| vec![card(s, Rank::Ace), card(s, Rank::Ace)])
+            .collect();
+        hand.extend(&[
+            card(Suit::Spade, Rank::Queen),
+            card(Suit::Spade, Rank::Queen),
+            card(Suit::Diamond, Rank::Jack),
+            card(Suit::Diamond, Rank::Jack),
+        ]);
+
+        assert_eq!(
+            find_meld(&hand, Suit::Club),
+            vec![Meld::Pinochle(2), Meld::Around(Rank::Ace, 2)]
+        );
+        assert_eq!(total(&find_meld(&hand, Suit::Club)), 1300);
+    }
+
+    #[test]
+    fn double_deck_multiples() {
+        let mut hand: Vec<Card> = (0..3).flat_map(|_| run(Suit::Spade)).collect();
+        hand.extend(Suit::iter().flat_map(|s| vec![card(s, Rank::King); 4]));
+        hand.extend(vec![card(Suit::Diamond, Rank::Jack); 4]);
+
+        let meld = find_meld(&hand, Suit::Spade);
+        assert_eq!(
+            meld,
+            vec![Meld::Run(3), Meld::Pinochle(3), Meld::Around(Rank::King, 4)]
+        );
+        assert_eq!(
+            meld.iter().map(Meld::value).collect::<Vec<_>>(),
+            vec![3000, 600, 1600]
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! A plain text notation for cards, hands and inputs, for fixtures, logs
+//! and bug reports. A card is its rank and then its suit, `9 J Q K T A` and
+//! `D C H S`, so `TH` is the ten of hearts. A hand is its cards separated by
+//! spaces. Inputs are written as
+//!
+//! ```text
+//! bid 275
+//! pass
+//! redeal
+//! moon
+//! trump H
+//! cards TH AS QS JD
+//! cards ?
+//! play AS
+//! declare double-pinochle
+//! throw-in
+//! next
+//! ```
+//!
+//! where `cards ?` is a pass of cards that aren't known.
+
+use super::core::{Card, Rank, Suit};
+use super::meld::Meld;
+use super::Input;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    text: String,
+    expected: &'static str,
+}
+
+impl ParseError {
+    fn new(text: &str, expected: &'static str) -> ParseError {
+        ParseError {
+            text: text.to_owned(),
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Expected {}, not {:?}", self.expected, self.text)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn rank_letter(rank: Rank) -> char {
+    match rank {
+        Rank::Nine => '9',
+        Rank::Jack => 'J',
+        Rank::Queen => 'Q',
+        Rank::King => 'K',
+        Rank::Ten => 'T',
+        Rank::Ace => 'A',
+    }
+}
+
+fn suit_letter(suit: Suit) -> char {
+    match suit {
+        Suit::Diamond => 'D',
+        Suit::Club => 'C',
+        Suit::Heart => 'H',
+        Suit::Spade => 'S',
+    }
+}
+
+fn parse_rank(s: &str) -> Result<Rank, ParseError> {
+    match s {
+        "9" => Ok(Rank::Nine),
+        "J" => Ok(Rank::Jack),
+        "Q" => Ok(Rank::Queen),
+        "K" => Ok(Rank::King),
+        "T" => Ok(Rank::Ten),
+        "A" => Ok(Rank::Ace),
+        _ => Err(ParseError::new(s, "a rank")),
+    }
+}
+
+fn parse_suit(s: &str) -> Result<Suit, ParseError> {
+    match s {
+        "D" => Ok(Suit::Diamond),
+        "C" => Ok(Suit::Club),
+        "H" => Ok(Suit::Heart),
+        "S" => Ok(Suit::Spade),
+        _ => Err(ParseError::new(s, "a suit")),
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", rank_letter(self.rank), suit_letter(self.suit))
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_ascii() || s.len() != 2 {
+            return Err(ParseError::new(s, "a card"));
+        }
+        Ok(Card {
+            rank: parse_rank(&s[..1])?,
+            suit: parse_suit(&s[1..])?,
+        })
+    }
+}
+
+/// Reads cards separated by whitespace, like `TH AS QS`
+pub fn parse_cards(s: &str) -> Result<Vec<Card>, ParseError> {
+    s.split_whitespace().map(str::parse).collect()
+}
+
+/// Writes cards separated by spaces, the way `parse_cards` reads them
+pub fn format_cards(cards: &[Card]) -> String {
+    let cards: Vec<String> = cards.iter().map(Card::to_string).collect();
+    cards.join(" ")
+}
+
+fn format_meld(meld: Meld) -> String {
+    let (name, n) = match meld {
+        Meld::Run(n) => ("run".to_owned(), n),
+        Meld::RoyalMarriage => ("royal-marriage".to_owned(), 1),
+        Meld::Marriage(suit) => (format!("marriage-{}", suit_letter(suit)), 1),
+        Meld::Pinochle(n) => ("pinochle".to_owned(), n),
+        Meld::Dix => ("dix".to_owned(), 1),
+        Meld::Around(rank, n) => (format!("around-{}", rank_letter(rank)), n),
+    };
+    match n {
+        1 => name,
+        2 => format!("double-{}", name),
+        3 => format!("triple-{}", name),
+        _ => format!("quadruple-{}", name),
+    }
+}
+
+fn parse_meld(s: &str) -> Result<Meld, ParseError> {
+    let (n, name) = match s.split_once('-') {
+        Some(("double", name)) => (2, name),
+        Some(("triple", name)) => (3, name),
+        Some(("quadruple", name)) => (4, name),
+        _ => (1, s),
+    };
+    let meld = match (name.split_once('-'), n) {
+        (None, _) if name == "run" => Meld::Run(n),
+        (None, _) if name == "pinochle" => Meld::Pinochle(n),
+        (None, 1) if name == "dix" => Meld::Dix,
+        (Some(("royal", "marriage")), 1) => Meld::RoyalMarriage,
+        (Some(("marriage", suit)), 1) => Meld::Marriage(parse_suit(suit)?),
+        (Some(("around", rank)), _) => Meld::Around(parse_rank(rank)?, n),
+        _ => return Err(ParseError::new(s, "a meld")),
+    };
+    Ok(meld)
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Input::Bid(amount) => write!(f, "bid {}", amount),
+            Input::Pass => write!(f, "pass"),
+            Input::RequestRedeal => write!(f, "redeal"),
+            Input::ShootTheMoon => write!(f, "moon"),
+            Input::SelectSuit(suit) => write!(f, "trump {}", suit_letter(*suit)),
+            Input::PassCards(Some(cards)) => write!(f, "cards {}", format_cards(cards)),
+            Input::PassCards(None) => write!(f, "cards ?"),
+            Input::Play(card) => write!(f, "play {}", card),
+            Input::Declare(meld) => write!(f, "declare {}", format_meld(*meld)),
+            Input::ThrowIn => write!(f, "throw-in"),
+            Input::Next => write!(f, "next"),
+        }
+    }
+}
+
+impl FromStr for Input {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (command, rest) = s.split_once(' ').unwrap_or((s, ""));
+        let rest = rest.trim();
+
+        let input = match (command, rest) {
+            ("bid", amount) => Input::Bid(
+                amount
+                    .parse()
+                    .map_err(|_| ParseError::new(amount, "an amount"))?,
+            ),
+            ("pass", "") => Input::Pass,
+            ("redeal", "") => Input::RequestRedeal,
+            ("moon", "") => Input::ShootTheMoon,
+            ("trump", suit) => Input::SelectSuit(parse_suit(suit)?),
+            ("cards", "?") => Input::PassCards(None),
+            ("cards", cards) => Input::PassCards(Some(parse_cards(cards)?)),
+            ("play", card) => Input::Play(card.parse()?),
+            ("declare", meld) => Input::Declare(parse_meld(meld)?),
+            ("throw-in", "") => Input::ThrowIn,
+            ("next", "") => Input::Next,
+            _ => return Err(ParseError::new(s, "an input")),
+        };
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cards_round_trip() {
+        let hand = parse_cards("TH AS  QS\tJD 9C").unwrap();
+        assert_eq!(hand.len(), 5);
+        assert_eq!(
+            hand[0],
+            Card {
+                suit: Suit::Heart,
+                rank: Rank::Ten
+            }
+        );
+        assert_eq!(
+            hand[4],
+            Card {
+                suit: Suit::Club,
+                rank: Rank::Nine
+            }
+        );
+        assert_eq!(format_cards(&hand), "TH AS QS JD 9C");
+
+        assert!("10H".parse::<Card>().is_err());
+        assert!("TX".parse::<Card>().is_err());
+        assert!(parse_cards("TH A").is_err());
+    }
+
+    #[test]
+    fn inputs_round_trip() {
+        let inputs = [
+            "bid 275",
+            "pass",
+            "redeal",
+            "moon",
+            "trump H",
+            "cards TH AS QS JD",
+            "cards ?",
+            "play AS",
+            "declare run",
+            "declare double-pinochle",
+            "declare royal-marriage",
+            "declare marriage-C",
+            "declare triple-around-K",
+            "declare dix",
+            "throw-in",
+            "next",
+        ];
+        for text in inputs.iter() {
+            let input: Input = text.parse().unwrap();
+            assert_eq!(&input.to_string(), text);
+        }
+
+        assert_eq!(
+            "declare double-around-A".parse(),
+            Ok(Input::Declare(Meld::Around(Rank::Ace, 2)))
+        );
+        assert!("bid lots".parse::<Input>().is_err());
+        assert!("declare double-dix".parse::<Input>().is_err());
+        assert!("pass TH".parse::<Input>().is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use super::core::Player;
+use super::dealer::Dealer;
+use super::error::GameError;
+use super::rules::RuleSet;
+use super::{Game, Input};
+use serde::{Deserialize, Serialize};
+
+/// A game kept as how it started and every input played since. Any earlier
+/// position can be rebuilt from it, and inputs can be taken back.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct GameRecord {
+    first_player: Player,
+    dealer: Dealer,
+    rules: RuleSet,
+    inputs: Vec<(Player, Input)>,
+    /// The game after every input, so playing doesn't replay the history
+    game: Game,
+}
+
+impl GameRecord {
+    pub fn new(first_player: Player, dealer: Dealer, rules: RuleSet) -> GameRecord {
+        GameRecord {
+            game: Game::with_dealer(first_player, dealer.clone(), rules.clone()),
+            first_player,
+            dealer,
+            rules,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// The game as it stands
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Every input played so far, in order
+    pub fn inputs(&self) -> &[(Player, Input)] {
+        &self.inputs
+    }
+
+    /// Plays `input` and records it. Inputs that aren't legal leave both
+    /// the game and the record as they were.
+    pub fn play(&mut self, player: Player, input: Input) -> Result<(), GameError> {
+        self.game.play(player, input.clone())?;
+        self.inputs.push((player, input));
+        Ok(())
+    }
+
+    /// The game as it was after the first `n` inputs
+    pub fn replay(&self, n: usize) -> Game {
+        let mut game =
+            Game::with_dealer(self.first_player, self.dealer.clone(), self.rules.clone());
+        for (player, input) in self.inputs.iter().take(n) {
+            game.play(*player, input.clone())
+                .expect("recorded inputs were legal when they were played");
+        }
+        game
+    }
+
+    /// Takes back the last `n` inputs
+    pub fn undo(&mut self, n: usize) -> Result<(), GameError> {
+        if n > self.inputs.len() {
+            return Err(GameError::NothingToUndo {
+                available: self.inputs.len(),
+            });
+        }
+
+        let remaining = self.inputs.len() - n;
+        self.game = self.replay(remaining);
+        self.inputs.truncate(remaining);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_rebuilds_the_game() -> Result<(), GameError> {
+        let mut record = GameRecord::new(Player::A, Dealer::Seeded(7), RuleSet::default());
+        let start = record.game().clone();
+
+        record.play(Player::A, Input::Bid(250))?;
+        record.play(Player::B, Input::Pass)?;
+        let after_two = record.game().clone();
+        assert!(record.play(Player::C, Input::Bid(250)).is_err());
+        record.play(Player::C, Input::Bid(275))?;
+        assert_eq!(record.inputs().len(), 3);
+        assert_eq!(&record.replay(3), record.game());
+
+        record.undo(1)?;
+        assert_eq!(record.game(), &after_two);
+        assert!(record.undo(3).is_err());
+        record.undo(2)?;
+        assert_eq!(record.game(), &start);
+        assert!(record.inputs().is_empty());
+
+        Ok(())
+    }
+}
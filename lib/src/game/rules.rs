@@ -0,0 +1,242 @@
+use super::core::{Deck, Layout, Player, Team};
+use super::error::GameError;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+/// House rules for a table. The defaults are standard single deck
+/// partnership pinochle.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct RuleSet {
+    /// The deck that is dealt, which also decides the size of the hands
+    pub deck: Deck,
+    /// The number of players and how they're split into teams
+    pub layout: Layout,
+    /// Cards dealt face down to no one. The auction winner picks them up and
+    /// then discards as many, or in a stock game they're the stock.
+    pub widow: usize,
+    /// Play two handed with a stock instead of an auction: the last card of
+    /// the stock is turned up for trump, and both players draw after each
+    /// trick until the stock runs out.
+    pub stock: bool,
+    /// The lowest opening bid
+    pub min_bid: usize,
+    /// Bids must go up from the minimum in steps of this size
+    pub bid_increment: usize,
+    /// How many cards the bidder's partner passes, and the bidder returns.
+    /// Players without partners don't pass.
+    pub pass_count: usize,
+    /// The game ends once a team has more than this many points
+    pub target_score: isize,
+    /// When set the dealer can't pass if everyone else has. Otherwise the
+    /// cards are thrown in and dealt by the next dealer.
+    pub dealer_stuck: bool,
+    /// A player holding at least this many nines may ask for a redeal
+    /// before they bid
+    pub redeal_nines: Option<usize>,
+    /// A player whose hand has no meld in any suit and no aces may ask for
+    /// a redeal before they bid
+    pub redeal_without_meld_or_aces: bool,
+    /// When set, a player may bid to shoot the moon, taking every trick,
+    /// instead of bidding points. The bidding team scores this much if
+    /// they take every trick, and goes set by this much if they don't.
+    pub shoot_the_moon: Option<usize>,
+    /// Shooting the moon wins the game outright instead of scoring
+    pub moon_wins_game: bool,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::for_deck(Deck::Single)
+    }
+}
+
+impl RuleSet {
+    /// The usual partnership rules for playing with `deck`. The triple and
+    /// quadruple decks are dealt to six and eight players.
+    pub fn for_deck(deck: Deck) -> Self {
+        match deck {
+            Deck::Single => RuleSet {
+                deck,
+                layout: Layout::PARTNERSHIP,
+                widow: 0,
+                stock: false,
+                min_bid: 250,
+                bid_increment: 25,
+                pass_count: 4,
+                target_score: 2000,
+                dealer_stuck: true,
+                redeal_nines: Some(5),
+                redeal_without_meld_or_aces: true,
+                shoot_the_moon: None,
+                moon_wins_game: false,
+            },
+            Deck::Double => RuleSet {
+                deck,
+                layout: Layout::PARTNERSHIP,
+                widow: 0,
+                stock: false,
+                min_bid: 500,
+                bid_increment: 10,
+                pass_count: 5,
+                target_score: 5000,
+                dealer_stuck: true,
+                // there are no nines to hold
+                redeal_nines: None,
+                redeal_without_meld_or_aces: true,
+                shoot_the_moon: None,
+                moon_wins_game: false,
+            },
+            Deck::Triple => RuleSet {
+                deck,
+                layout: Layout::SIX_HANDED,
+                widow: 0,
+                stock: false,
+                min_bid: 400,
+                bid_increment: 10,
+                pass_count: 3,
+                target_score: 3000,
+                dealer_stuck: true,
+                redeal_nines: Some(5),
+                redeal_without_meld_or_aces: true,
+                shoot_the_moon: None,
+                moon_wins_game: false,
+            },
+            Deck::Quadruple => RuleSet {
+                deck,
+                layout: Layout::EIGHT_HANDED,
+                widow: 0,
+                stock: false,
+                min_bid: 500,
+                bid_increment: 10,
+                pass_count: 3,
+                target_score: 3000,
+                dealer_stuck: true,
+                redeal_nines: Some(5),
+                redeal_without_meld_or_aces: true,
+                shoot_the_moon: None,
+                moon_wins_game: false,
+            },
+        }
+    }
+
+    /// Three handed cutthroat: everyone plays for themselves, and the
+    /// bidder takes a three card widow instead of passing
+    pub fn cutthroat() -> Self {
+        RuleSet {
+            layout: Layout::CUTTHROAT,
+            widow: 3,
+            pass_count: 0,
+            min_bid: 250,
+            bid_increment: 10,
+            target_score: 1500,
+            ..RuleSet::default()
+        }
+    }
+
+    /// Two handed pinochle, drawing from a stock of the 24 cards that
+    /// aren't dealt
+    pub fn two_handed() -> Self {
+        RuleSet {
+            layout: Layout::HEADS_UP,
+            widow: 24,
+            stock: true,
+            pass_count: 0,
+            target_score: 1000,
+            ..RuleSet::default()
+        }
+    }
+
+    /// Checks the rules can be played, for rules that come from a client.
+    /// Rules that fail would otherwise panic part way through a game.
+    pub fn validate(&self) -> Result<(), GameError> {
+        let invalid = |reason: &str| Err(GameError::InvalidRules(reason.to_owned()));
+        let players = self.layout.number_of_players();
+        let teams = self.layout.number_of_teams();
+        let cards = self.deck.cards().len();
+
+        if players < 2 || players > Player::iter().count() {
+            return invalid("unsupported number of players");
+        }
+        if teams < 2 || teams > Team::iter().count() || !players.is_multiple_of(teams) {
+            return invalid("players can't be split evenly into teams");
+        }
+        // partners sit across from each other, so they have to be on the same team
+        if players > teams && !(players / 2).is_multiple_of(teams) {
+            return invalid("partners would be on different teams");
+        }
+        if self.bid_increment == 0 {
+            return invalid("the bid increment must be more than zero");
+        }
+        if self.widow >= cards || !(cards - self.widow).is_multiple_of(players) {
+            return invalid("the cards can't be dealt evenly");
+        }
+        if self.pass_count > self.hand_size() || (self.widow > self.hand_size() && !self.stock) {
+            return invalid("more cards to pass or discard than are in a hand");
+        }
+        // partners pass instead of discarding, so the bidder would keep the widow
+        if self.widow > 0 && !self.stock && self.layout.partner(Player::A).is_some() {
+            return invalid("only players without partners can take a widow");
+        }
+        if self.stock && players != 2 {
+            return invalid("only two players can play with a stock");
+        }
+        if self.redeal_nines == Some(0) {
+            return invalid("every hand would be a misdeal");
+        }
+        Ok(())
+    }
+
+    /// The number of cards dealt to each player
+    pub fn hand_size(&self) -> usize {
+        (self.deck.cards().len() - self.widow) / self.layout.number_of_players()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_rules() {
+        assert_eq!(RuleSet::default().validate(), Ok(()));
+        assert_eq!(RuleSet::cutthroat().validate(), Ok(()));
+        assert_eq!(RuleSet::two_handed().validate(), Ok(()));
+        for deck in Deck::iter() {
+            assert_eq!(RuleSet::for_deck(deck).validate(), Ok(()));
+        }
+
+        let unplayable = [
+            RuleSet {
+                bid_increment: 0,
+                ..RuleSet::default()
+            },
+            RuleSet {
+                widow: 60,
+                ..RuleSet::default()
+            },
+            RuleSet {
+                widow: 5,
+                ..RuleSet::default()
+            },
+            RuleSet {
+                widow: 4,
+                ..RuleSet::default()
+            },
+            RuleSet {
+                pass_count: 13,
+                ..RuleSet::default()
+            },
+            RuleSet {
+                stock: true,
+                ..RuleSet::default()
+            },
+        ];
+        for rules in unplayable.iter() {
+            assert!(
+                matches!(rules.validate(), Err(GameError::InvalidRules(_))),
+                "{:?}",
+                rules
+            );
+        }
+    }
+}
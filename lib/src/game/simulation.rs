@@ -0,0 +1,216 @@
+//! Plays whole games with random legal inputs under every set of rules,
+//! checking after each input that the engine still holds together and that
+//! every game finishes
+
+use super::core::*;
+use super::dealer::Dealer;
+use super::rules::RuleSet;
+use super::states::{count_counters, Project, Viewer, LAST_TRICK_BONUS};
+use super::{Game, Input};
+use crate::bot::{play_input, Heuristic, Strategy};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Games played under each set of rules, unless `SIMULATED_GAMES` asks for
+/// more. The ignored `thousands_of_random_games` plays more, but is slow in
+/// a debug build.
+const GAMES_PER_RULESET: u64 = 20;
+/// Every game has to finish within this many inputs. The longest, six and
+/// eight handed, take up to around 3000.
+const MAX_INPUTS: usize = 10000;
+/// One auction input in this many is random rather than the bot's
+const RANDOM_BIDS: u32 = 40;
+
+/// Every variant, with targets and minimum bids low enough that games get to
+/// the end
+fn rule_sets() -> Vec<RuleSet> {
+    let rule_sets = vec![
+        RuleSet::default(),
+        RuleSet::for_deck(Deck::Double),
+        RuleSet::for_deck(Deck::Triple),
+        RuleSet::for_deck(Deck::Quadruple),
+        RuleSet::cutthroat(),
+        RuleSet::two_handed(),
+        RuleSet {
+            shoot_the_moon: Some(1000),
+            ..RuleSet::default()
+        },
+    ];
+    rule_sets
+        .into_iter()
+        .map(|rules| RuleSet {
+            target_score: rules.target_score / 4,
+            min_bid: rules.min_bid / 2,
+            ..rules
+        })
+        .collect()
+}
+
+fn sorted(cards: impl Iterator<Item = Card>) -> Vec<Card> {
+    let mut cards: Vec<Card> = cards.collect();
+    cards.sort_by_key(|c| (c.suit as usize, c.rank));
+    cards
+}
+
+/// Random legal inputs, except that the heuristic bot runs most auctions
+/// and decides whether to throw in. Random bidders and throw-ins go set so
+/// often that the scores sink and games never end.
+fn choose(game: &Game, player: Player, rng: &mut ChaCha8Rng) -> Input {
+    let mut inputs = game.legal_inputs(player);
+    if let Game::Bidding(_) = game {
+        if !rng.gen_ratio(1, RANDOM_BIDS) {
+            return Heuristic.choose(game, player);
+        }
+    }
+    if inputs.contains(&Input::ThrowIn) {
+        if Heuristic.choose(game, player) == Input::ThrowIn {
+            return Input::ThrowIn;
+        }
+        inputs.retain(|i| *i != Input::ThrowIn);
+    }
+    inputs.choose(rng).unwrap().clone()
+}
+
+fn check_state(game: &Game, rules: &RuleSet) {
+    let layout = rules.layout;
+    let can_play = layout.players().filter(|p| game.can_play(*p)).count();
+    match game {
+        Game::Finished(_) => assert_eq!(can_play, 0),
+        Game::FinishedRound(_) => assert_eq!(can_play, layout.number_of_players()),
+        _ => assert_eq!(can_play, 1, "exactly one player can act"),
+    }
+
+    if let Game::Finished(_) = game {
+        return;
+    }
+
+    // no card is created or lost
+    let cards = game.cards();
+    assert!(cards.iter().all(Option::is_some));
+    assert_eq!(
+        sorted(cards.iter().flatten().copied()),
+        sorted(rules.deck.cards().into_iter())
+    );
+
+    for player in layout.players() {
+        let view = game.project(player);
+        assert_eq!(view.hand(player), game.hand(player));
+        for other in layout.players().filter(|p| *p != player) {
+            assert!(view.hand(other).unwrap().iter().all(Option::is_none));
+        }
+        assert!(view.widow().unwrap().iter().all(Option::is_none));
+        assert_eq!(view.dealer(), &Dealer::Hidden);
+        assert_eq!(view.cards().len(), cards.len());
+    }
+
+    let spectator = game.project_for(Viewer::Spectator);
+    for player in layout.players() {
+        assert!(spectator.hand(player).unwrap().iter().all(Option::is_none));
+    }
+    assert_eq!(spectator.score(Team::Red), game.score(Team::Red));
+
+    // players can work out what they can do from what they can see
+    for player in layout.players().filter(|p| game.can_play(*p)) {
+        let view = game.project(player);
+        assert_eq!(view.legal_inputs(player), game.legal_inputs(player));
+    }
+}
+
+fn check_transition(previous: &Game, game: &Game, rules: &RuleSet) {
+    let layout = rules.layout;
+    if let Game::FinishedRound(round) = game {
+        if round.thrown_in() {
+            let (bidder, bid) = round.winning_bid().unwrap();
+            for team in layout.teams() {
+                let expected = if team == layout.team(bidder) {
+                    -(bid as isize)
+                } else {
+                    round.team_meld(team) as isize
+                };
+                assert_eq!(round.round_score(team), expected);
+                assert_eq!(game.score(team), previous.score(team) + expected);
+            }
+            return;
+        }
+
+        // the taken piles hold every card, and the counters in them and
+        // the last trick are all counted
+        let taken: Vec<Card> = layout.teams().flat_map(|t| round.taken(t)).collect();
+        assert_eq!(taken.len(), rules.deck.cards().len());
+        let counted: usize = layout.teams().map(|t| round.counters(t)).sum();
+        assert_eq!(counted, count_counters(&taken) + LAST_TRICK_BONUS);
+
+        // every card but the bidder's discards was played to a trick
+        let tricks = round.tricks();
+        let discards = if rules.stock { 0 } else { rules.widow };
+        assert_eq!(
+            tricks.len() * layout.number_of_players() + discards,
+            rules.deck.cards().len()
+        );
+        let last_trick = layout.team(tricks.last().unwrap().winner);
+
+        for team in layout.teams() {
+            let bonus = round.counters(team) - count_counters(&round.taken(team));
+            let expected = if team == last_trick {
+                LAST_TRICK_BONUS
+            } else {
+                0
+            };
+            assert_eq!(bonus, expected);
+            assert_eq!(
+                game.score(team),
+                previous.score(team) + round.round_score(team)
+            );
+        }
+        return;
+    }
+
+    // scores only change when a round is scored
+    for team in layout.teams() {
+        assert_eq!(game.score(team), previous.score(team));
+    }
+}
+
+fn play_game(rules: RuleSet, seed: u64) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut game = Game::with_dealer(Player::A, Dealer::Seeded(seed), rules.clone());
+    let mut inputs = 0;
+
+    loop {
+        check_state(&game, &rules);
+        if let Game::Finished(_) = game {
+            break;
+        }
+        assert!(inputs < MAX_INPUTS, "the game didn't finish");
+        inputs += 1;
+
+        let previous = game.clone();
+        play_input(&mut game, |game, player| choose(game, player, &mut rng));
+        check_transition(&previous, &game, &rules);
+    }
+}
+
+fn play_games(games: u64) {
+    for rules in rule_sets() {
+        for seed in 0..games {
+            let result = std::panic::catch_unwind(|| play_game(rules.clone(), seed));
+            assert!(result.is_ok(), "{:?} failed with seed {}", rules, seed);
+        }
+    }
+}
+
+#[test]
+fn random_games_hold_together() {
+    let games = std::env::var("SIMULATED_GAMES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(GAMES_PER_RULESET);
+    play_games(games);
+}
+
+#[test]
+#[ignore]
+fn thousands_of_random_games() {
+    play_games(1000);
+}
@@ -1,23 +1,42 @@
 use super::core::*;
+use super::dealer::Dealer;
+use super::error::GameError;
+use super::meld::{self, Meld};
+use super::rules::RuleSet;
 use either::Either;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::iter;
+use strum::IntoEnumIterator;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-pub struct BiddingState(Player);
+pub struct BiddingState {
+    turn: Player,
+    /// The hand thrown in for the redeal that started this auction
+    pub misdeal: Option<Misdeal>,
+}
 
 impl Project for BiddingState {
-    fn project(&self, _: Player) -> Self {
-        Self(self.0)
+    fn project_for(&self, _: Viewer) -> Self {
+        Self {
+            turn: self.turn,
+            misdeal: self.misdeal.clone(),
+        }
     }
 }
 
+/// A hand that was thrown in for a redeal, which is shown to everyone
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Misdeal {
+    pub player: Player,
+    pub hand: Vec<Card>,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct SelectingTrumpState(Player);
 
 impl Project for SelectingTrumpState {
-    fn project(&self, _: Player) -> Self {
+    fn project_for(&self, _: Viewer) -> Self {
         let SelectingTrumpState(a) = self;
         SelectingTrumpState(*a)
     }
@@ -30,7 +49,7 @@ pub struct PassingCardsState {
 }
 
 impl Project for PassingCardsState {
-    fn project(&self, _: Player) -> Self {
+    fn project_for(&self, _: Viewer) -> Self {
         Self {
             turn: self.turn,
             trump: self.trump,
@@ -45,7 +64,37 @@ pub struct ReturningCardsState {
 }
 
 impl Project for ReturningCardsState {
-    fn project(&self, _: Player) -> Self {
+    fn project_for(&self, _: Viewer) -> Self {
+        Self {
+            turn: self.turn,
+            trump: self.trump,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DiscardingState {
+    turn: Player,
+    trump: Suit,
+}
+
+impl Project for DiscardingState {
+    fn project_for(&self, _: Viewer) -> Self {
+        Self {
+            turn: self.turn,
+            trump: self.trump,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct MeldingState {
+    turn: Player,
+    trump: Suit,
+}
+
+impl Project for MeldingState {
+    fn project_for(&self, _: Viewer) -> Self {
         Self {
             turn: self.turn,
             trump: self.trump,
@@ -53,61 +102,227 @@ impl Project for ReturningCardsState {
     }
 }
 
+/// A trick once everyone has played to it
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Trick {
+    pub leader: Player,
+    /// The cards in the order they were played, starting with the leader's
+    pub cards: Vec<Card>,
+    pub winner: Player,
+}
+
+impl Trick {
+    /// The card played by `player`, if they played to the trick
+    pub fn card(&self, player: Player, layout: Layout) -> Option<Card> {
+        (0..self.cards.len())
+            .find(|i| layout.seat_after(self.leader, *i) == player)
+            .map(|i| self.cards[i])
+    }
+}
+
+/// Collects the cards in the play area into a trick led by `leader`
+fn collect_trick(play_area: &mut Vec<Card>, leader: Player, trump: Suit, layout: Layout) -> Trick {
+    let index = winning_index(play_area, trump);
+    Trick {
+        leader,
+        cards: std::mem::take(play_area),
+        winner: layout.seat_after(leader, index),
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct PlayingState {
     pub play_area: Vec<Card>,
-    pub taken: [Vec<Card>; NUMBER_OF_TEAMS],
+    pub taken: Vec<Vec<Card>>,
+    /// Every trick played this round, in order
+    pub tricks: Vec<Trick>,
     pub trump: Suit,
     turn: Player,
 }
 
 impl Project for PlayingState {
-    fn project(&self, _: Player) -> Self {
+    fn project_for(&self, _: Viewer) -> Self {
         Self {
             turn: self.turn,
             play_area: self.play_area.clone(),
             taken: self.taken.clone(),
-            trump: self.trump.clone(),
+            tricks: self.tricks.clone(),
+            trump: self.trump,
         }
     }
 }
 
+/// Two handed play while there are still cards in the stock. Any card may
+/// be played, and the winner of each trick may declare meld before both
+/// players draw.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct StockState {
+    pub play_area: Vec<Card>,
+    pub taken: Vec<Vec<Card>>,
+    pub tricks: Vec<Trick>,
+    pub trump: Suit,
+    /// The card turned up for trump, which is the last card drawn
+    pub trump_card: Option<Card>,
+    /// Melded cards lying face up in front of each player, with the melds
+    /// they've been used in. They're still part of the player's hand.
+    pub shown: PlayerMap<Vec<(Card, Vec<Meld>)>>,
+    turn: Player,
+}
+
+impl Project for StockState {
+    fn project_for(&self, _: Viewer) -> Self {
+        self.clone()
+    }
+}
+
+/// The winner of a trick deciding whether to declare meld
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct DeclaringState(StockState);
+
+impl Project for DeclaringState {
+    fn project_for(&self, _: Viewer) -> Self {
+        self.clone()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct FinishedRoundState {
-    pub taken: [Vec<Card>; NUMBER_OF_TEAMS],
+    /// Cards taken by each team, including the bidder's discards
+    pub taken: Vec<Vec<Card>>,
+    /// Every trick played in the round, in order
+    pub tricks: Vec<Trick>,
     pub trump: Suit,
+    /// Counters taken by each team, including the last trick bonus
+    pub counters: Vec<usize>,
+    /// Points added to each team's game score: meld plus counters, or minus
+    /// the bid when the bidding team went set
+    pub round_scores: Vec<isize>,
+    /// The bidder conceded the hand before it was played
+    pub thrown_in: bool,
 }
 
 impl Project for FinishedRoundState {
-    fn project(&self, _: Player) -> Self {
+    fn project_for(&self, _: Viewer) -> Self {
         Self {
             taken: self.taken.clone(),
-            trump: self.trump.clone(),
+            tricks: self.tricks.clone(),
+            trump: self.trump,
+            counters: self.counters.clone(),
+            round_scores: self.round_scores.clone(),
+            thrown_in: self.thrown_in,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Finished {
+    scores: Vec<isize>,
+    winner: Team,
+    rounds: usize,
+    rules: RuleSet,
+    dealer: Dealer,
+}
+
+impl Finished {
+    pub(crate) fn placeholder() -> Finished {
+        Finished {
+            scores: Vec::new(),
+            winner: Team::Red,
+            rounds: 0,
+            rules: RuleSet::default(),
+            dealer: Dealer::Hidden,
         }
     }
+
+    pub fn score(&self, team: Team) -> isize {
+        self.scores[team as usize]
+    }
+
+    pub fn winner(&self) -> Team {
+        self.winner
+    }
+
+    /// The number of rounds played in the game
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// The dealer the game was played with. Once the game is over this is
+    /// no longer hidden from the players.
+    pub fn dealer(&self) -> &Dealer {
+        &self.dealer
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Finished([usize; NUMBER_OF_TEAMS]);
+impl Project for Finished {
+    fn project_for(&self, _: Viewer) -> Self {
+        self.clone()
+    }
+}
 
 pub type Bidding = Active<BiddingState>;
 pub type SelectingTrump = Active<SelectingTrumpState>;
 pub type PassingCards = Active<PassingCardsState>;
 pub type ReturningCards = Active<ReturningCardsState>;
+pub type Discarding = Active<DiscardingState>;
+pub type Melding = Active<MeldingState>;
 pub type Playing = Active<PlayingState>;
+pub type Stock = Active<StockState>;
+pub type Declaring = Active<DeclaringState>;
 pub type FinishedRound = Active<FinishedRoundState>;
 
+/// A bid made in the auction
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Bid {
+    Points(usize),
+    /// A bid to take every trick, which ends the auction. It's worth what
+    /// the rules say shooting the moon is worth.
+    Moon,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Active<T> {
     hands: PlayerMap<Vec<Option<Card>>>,
-    scores: [usize; NUMBER_OF_TEAMS],
-    bids: Vec<Option<usize>>,
-    initial_bidder: Player,
+    /// The widow until the bidder picks it up, and then the bidder's
+    /// discards. In a stock game this is the stock.
+    widow: Vec<Option<Card>>,
+    scores: Vec<isize>,
+    /// Every bid in the auction, with `None` for a pass
+    bids: Vec<(Player, Option<Bid>)>,
+    meld: PlayerMap<Vec<Meld>>,
+    /// The seat that dealt this hand
+    dealer_seat: Player,
+    rounds: usize,
+    deals: usize,
+    rules: RuleSet,
+    dealer: Dealer,
     state: T,
 }
 
+/// Who a projection of the game is for
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub enum Viewer {
+    /// A seated player, who sees their own hand
+    Player(Player),
+    /// Someone watching, who sees everything on the table but none of the
+    /// hands
+    Spectator,
+}
+
 pub trait Project {
-    fn project(&self, player: Player) -> Self;
+    fn project_for(&self, viewer: Viewer) -> Self;
+
+    /// What `player` can see of the game
+    fn project(&self, player: Player) -> Self
+    where
+        Self: Sized,
+    {
+        self.project_for(Viewer::Player(player))
+    }
 }
 
 impl<T> Active<T>
@@ -118,23 +333,191 @@ where
         self.hands.get_value(p)
     }
 
+    /// The widow or the bidder's discards, or in a stock game the stock.
+    /// None of it is known in a projection.
+    pub fn widow(&self) -> &[Option<Card>] {
+        &self.widow
+    }
+
+    /// Fills in the unknown cards of each hand and then of the widow, in
+    /// order, for trying out a guess at the cards a player can't see
+    pub fn reveal(&mut self, hands: &PlayerMap<Vec<Card>>, widow: &[Card]) {
+        for (p, cards) in hands.iter() {
+            let unknown = self.hand_mut(p).iter_mut().filter(|c| c.is_none());
+            for (slot, card) in unknown.zip(cards) {
+                *slot = Some(*card);
+            }
+        }
+        let unknown = self.widow.iter_mut().filter(|c| c.is_none());
+        for (slot, card) in unknown.zip(widow) {
+            *slot = Some(*card);
+        }
+    }
+
     fn hand_mut(&mut self, p: Player) -> &mut Vec<Option<Card>> {
         self.hands.get_value_mut(p)
     }
 
-    pub fn score(&self, team: Team) -> usize {
+    pub fn score(&self, team: Team) -> isize {
         self.scores[team as usize]
     }
 
-    fn score_mut(&mut self, team: Team) -> &mut usize {
+    fn score_mut(&mut self, team: Team) -> &mut isize {
         &mut self.scores[team as usize]
     }
 
-    pub fn bids(&self) -> &[Option<usize>] {
+    /// Every bid and pass made in the auction, in order
+    pub fn bids(&self) -> &[(Player, Option<Bid>)] {
         &self.bids
     }
+
+    /// The highest bid so far and the player who made it. Shooting the
+    /// moon beats every bid in points, and is worth what the rules say it
+    /// is.
+    pub fn winning_bid(&self) -> Option<(Player, usize)> {
+        if let (Some(p), Some(amount)) = (self.moon_bidder(), self.rules.shoot_the_moon) {
+            return Some((p, amount));
+        }
+        self.bids
+            .iter()
+            .filter_map(|(p, bid)| match bid {
+                Some(Bid::Points(amount)) => Some((*p, *amount)),
+                _ => None,
+            })
+            .max_by_key(|(_, bid)| *bid)
+    }
+
+    /// The player who bid to shoot the moon, if anyone did
+    pub fn moon_bidder(&self) -> Option<Player> {
+        self.bids
+            .iter()
+            .find(|(_, bid)| *bid == Some(Bid::Moon))
+            .map(|(p, _)| *p)
+    }
+
+    /// Whether `p` has passed and so dropped out of the auction
+    pub fn has_passed(&self, p: Player) -> bool {
+        self.bids.iter().any(|(q, bid)| *q == p && bid.is_none())
+    }
+
+    /// The seat that dealt this hand, which moves one to the left each deal
+    pub fn dealer_seat(&self) -> Player {
+        self.dealer_seat
+    }
+
+    /// The player to the dealer's left, who bids first, or in a stock game
+    /// leads to the first trick
     pub fn initial_bidder(&self) -> Player {
-        self.initial_bidder
+        self.layout().next(self.dealer_seat)
+    }
+
+    /// The number of rounds that have been scored so far
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    /// The number of times cards have been dealt, including deals that were
+    /// thrown in without being played
+    pub fn deals(&self) -> usize {
+        self.deals
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.rules.layout
+    }
+
+    /// The dealer of this game, which is `Dealer::Hidden` in a projection
+    pub fn dealer(&self) -> &Dealer {
+        &self.dealer
+    }
+
+    pub fn meld(&self, p: Player) -> &[Meld] {
+        self.meld.get_value(p)
+    }
+
+    pub fn team_meld(&self, team: Team) -> usize {
+        self.meld
+            .iter()
+            .filter(|(p, _)| self.layout().team(*p) == team)
+            .map(|(_, m)| meld::total(m))
+            .sum()
+    }
+
+    /// Passes the deal to the left and deals a new hand, in whichever way
+    /// the rules play it.
+    fn next_deal(self) -> Either<Bidding, Stock> {
+        if self.rules.stock {
+            Either::Right(self.deal().turn_up_trump())
+        } else {
+            Either::Left(self.deal())
+        }
+    }
+
+    /// Passes the deal to the left and deals a new hand to bid on, keeping
+    /// the scores and rules of this one.
+    fn deal(self) -> Bidding {
+        let dealer_seat = self.layout().next(self.dealer_seat);
+        self.deal_from(dealer_seat)
+    }
+
+    fn deal_from(self, dealer_seat: Player) -> Bidding {
+        let deals = self.deals + 1;
+        let (hands, widow) = self.dealer.deal(deals, dealer_seat, &self.rules);
+        let mut next = Bidding::start(dealer_seat, hands, widow, self.rules, self.dealer);
+        next.scores = self.scores;
+        next.rounds = self.rounds;
+        next.deals = deals;
+        next
+    }
+
+    /// Ends the round without playing it, for a bidder who can't make the
+    /// bid. The bidding team loses the bid and the other teams score their
+    /// meld.
+    fn concede(mut self, trump: Suit) -> FinishedRound {
+        let layout = self.layout();
+        let teams = layout.number_of_teams();
+        let (bidder, bid) = self.winning_bid().expect("only a bidder can throw in");
+
+        let mut round_scores = vec![0; teams];
+        for team in layout.teams() {
+            round_scores[team as usize] = if team == layout.team(bidder) {
+                -(bid as isize)
+            } else {
+                self.team_meld(team) as isize
+            };
+            *self.score_mut(team) += round_scores[team as usize];
+        }
+        self.rounds += 1;
+
+        let state = FinishedRoundState {
+            taken: vec![Vec::new(); teams],
+            tricks: Vec::new(),
+            trump,
+            counters: vec![0; teams],
+            round_scores,
+            thrown_in: true,
+        };
+        self.with_state(state)
+    }
+
+    fn with_state<U>(self, state: U) -> Active<U> {
+        Active {
+            hands: self.hands,
+            widow: self.widow,
+            scores: self.scores,
+            bids: self.bids,
+            meld: self.meld,
+            dealer_seat: self.dealer_seat,
+            rounds: self.rounds,
+            deals: self.deals,
+            rules: self.rules,
+            dealer: self.dealer,
+            state,
+        }
     }
 }
 
@@ -142,92 +525,219 @@ impl<T> Project for Active<T>
 where
     T: Project,
 {
-    fn project(&self, player: Player) -> Self {
+    fn project_for(&self, viewer: Viewer) -> Self {
+        let hidden = |cards: &Vec<Option<Card>>| cards.iter().map(|_| None).collect();
         Self {
-            hands: self.hands.map(|p, x| {
-                if p == player {
-                    x.clone()
-                } else {
-                    x.iter().map(|_| None).collect()
-                }
+            hands: self.hands.map(|p, x| match viewer {
+                Viewer::Player(player) if p == player => x.clone(),
+                _ => hidden(x),
             }),
+            widow: hidden(&self.widow),
             scores: self.scores.clone(),
             bids: self.bids.clone(),
-            initial_bidder: self.initial_bidder,
-            state: self.state.project(player),
+            meld: self.meld.clone(),
+            dealer_seat: self.dealer_seat,
+            rounds: self.rounds,
+            deals: self.deals,
+            rules: self.rules.clone(),
+            dealer: Dealer::Hidden,
+            state: self.state.project_for(viewer),
         }
     }
 }
 
 impl Bidding {
     pub fn new(first_player: Player, hands: PlayerMap<Vec<Option<Card>>>) -> Bidding {
+        Bidding::with_rules(first_player, hands, RuleSet::default())
+    }
+
+    /// Starts a game with the given hands and no widow. Later deals are
+    /// shuffled by a randomly seeded dealer.
+    pub fn with_rules(
+        first_player: Player,
+        hands: PlayerMap<Vec<Option<Card>>>,
+        rules: RuleSet,
+    ) -> Bidding {
+        let dealer_seat = rules.layout.previous(first_player);
+        Bidding::start(dealer_seat, hands, Vec::new(), rules, Dealer::random())
+    }
+
+    /// Starts a game with every deal, including the first, made by `dealer`.
+    /// The first hand is dealt by the player to the right of `first_player`.
+    pub fn with_dealer(first_player: Player, dealer: Dealer, rules: RuleSet) -> Bidding {
+        let dealer_seat = rules.layout.previous(first_player);
+        let (hands, widow) = dealer.deal(0, dealer_seat, &rules);
+        Bidding::start(dealer_seat, hands, widow, rules, dealer)
+    }
+
+    fn start(
+        dealer_seat: Player,
+        hands: PlayerMap<Vec<Option<Card>>>,
+        widow: Vec<Option<Card>>,
+        rules: RuleSet,
+        dealer: Dealer,
+    ) -> Bidding {
         Bidding {
-            hands: hands,
+            hands,
+            widow,
             bids: Vec::new(),
-            scores: [0, 0],
-            initial_bidder: first_player,
-            state: BiddingState(first_player),
+            meld: PlayerMap::from_fn(rules.layout, |_| vec![]),
+            scores: vec![0; rules.layout.number_of_teams()],
+            dealer_seat,
+            rounds: 0,
+            deals: 0,
+            state: BiddingState {
+                turn: rules.layout.next(dealer_seat),
+                misdeal: None,
+            },
+            rules,
+            dealer,
         }
     }
 
-    pub fn pass(self) -> Either<(Bidding, Option<&'static str>), SelectingTrump> {
+    pub fn pass(self) -> Either<(Bidding, Option<GameError>), SelectingTrump> {
         self.do_bid(None)
     }
 
-    pub fn bid(self, amount: usize) -> Either<(Bidding, Option<&'static str>), SelectingTrump> {
+    pub fn bid(self, amount: usize) -> Either<(Bidding, Option<GameError>), SelectingTrump> {
         self.do_bid(Some(amount))
     }
 
+    /// Bids to take every trick, which no one can bid over
+    pub fn shoot_the_moon(mut self) -> Either<(Bidding, Option<GameError>), SelectingTrump> {
+        if self.rules.shoot_the_moon.is_none() {
+            return Either::Left((self, Some(GameError::MoonNotAllowed)));
+        }
+        let turn = self.state.turn;
+        self.bids.push((turn, Some(Bid::Moon)));
+        Either::Right(self.win_auction(turn))
+    }
+
     pub fn turn(&self) -> Player {
-        self.state.0
+        self.state.turn
+    }
+
+    /// The hand thrown in for the redeal that started this auction
+    pub fn misdeal(&self) -> Option<&Misdeal> {
+        self.state.misdeal.as_ref()
+    }
+
+    /// Whether the player whose turn it is can ask for a redeal: they
+    /// haven't bid yet, and their hand meets one of the rules for a
+    /// misdeal.
+    pub fn check_redeal(&self) -> Result<(), GameError> {
+        let turn = self.state.turn;
+        if self.bids.iter().any(|(p, _)| *p == turn) {
+            return Err(GameError::AlreadyBid);
+        }
+        let hand: Option<Vec<Card>> = self.hand(turn).iter().copied().collect();
+        let hand = hand.ok_or(GameError::CardsNotKnown)?;
+
+        let nines = hand.iter().filter(|c| c.rank == Rank::Nine).count();
+        let too_many_nines = self.rules.redeal_nines.is_some_and(|n| nines >= n);
+        let no_meld = Suit::iter().all(|trump| meld::find_meld(&hand, trump).is_empty());
+        let no_aces = !hand.iter().any(|c| c.rank == Rank::Ace);
+        let worthless = self.rules.redeal_without_meld_or_aces && no_meld && no_aces;
+
+        if too_many_nines || worthless {
+            Ok(())
+        } else {
+            Err(GameError::NotAMisdeal)
+        }
+    }
+
+    /// Throws in the hands after showing the hand of the player asking,
+    /// and deals again from the same seat
+    pub fn redeal(self) -> Result<Bidding, GameError> {
+        self.check_redeal()?;
+        let player = self.state.turn;
+        let hand = self.hand(player).iter().flatten().copied().collect();
+        let dealer_seat = self.dealer_seat;
+
+        let mut next = self.deal_from(dealer_seat);
+        next.state.misdeal = Some(Misdeal { player, hand });
+        Ok(next)
+    }
+
+    /// The lowest bid that can be made now
+    pub fn min_bid(&self) -> usize {
+        self.winning_bid()
+            .map_or(self.rules.min_bid, |(_, a)| a + self.rules.bid_increment)
+    }
+
+    /// The highest bid worth listing: every counter in the deck and the
+    /// last trick, on top of the best meld in the bidder's hand. Higher
+    /// bids are legal, but can't be made without help. It's never below
+    /// `min_bid`, since a bidder who can't pass has to bid something.
+    pub fn max_bid(&self) -> usize {
+        let hand: Vec<Card> = self.hand(self.turn()).iter().filter_map(|c| *c).collect();
+        let meld = Suit::iter()
+            .map(|trump| meld::total(&meld::find_meld(&hand, trump)))
+            .max()
+            .unwrap_or(0);
+        let most = count_counters(&self.rules.deck.cards()) + LAST_TRICK_BONUS + meld;
+        most.max(self.min_bid())
+    }
+
+    /// Whether the player whose turn it is may pass. When the dealer is
+    /// stuck, the dealer can't pass if no one has bid.
+    pub fn can_pass(&self) -> bool {
+        let turn = self.state.turn;
+        let others_passed = self
+            .layout()
+            .players()
+            .all(|p| p == turn || self.has_passed(p));
+        let stuck = self.rules.dealer_stuck && turn == self.dealer_seat;
+        !(stuck && others_passed && self.winning_bid().is_none())
     }
 
     fn do_bid(
         mut self,
         amount: Option<usize>,
-    ) -> Either<(Bidding, Option<&'static str>), SelectingTrump> {
+    ) -> Either<(Bidding, Option<GameError>), SelectingTrump> {
         if let Some(amount) = amount {
-            let min_bid = 250;
-            if amount < min_bid {
-                return Either::Left((self, Some("Must bid be at least 250")));
-            }
-            if (amount - min_bid) % 25 != 0 {
-                return Either::Left((self, Some("Must bid in increments of 25")));
+            let min = self.min_bid();
+            if amount < min {
+                return Either::Left((self, Some(GameError::BidTooLow { min })));
             }
-            if self.bids.iter().any(|a| a.map_or(false, |a| a >= amount)) {
-                return Either::Left((self, Some("Bid must be higher than any bid")));
+            if !(amount - self.rules.min_bid).is_multiple_of(self.rules.bid_increment) {
+                return Either::Left((self, Some(GameError::BadIncrement)));
             }
         }
 
-        if amount.is_none()
-            && self.bids.len() == NUMBER_OF_PLAYERS - 1
-            && self.bids.iter().filter_map(|x| *x).count() == 0
-        {
-            return Either::Left((self, Some("Last bidder must not pass if there's no bid")));
+        if amount.is_none() && !self.can_pass() {
+            return Either::Left((self, Some(GameError::MustBid)));
         }
 
-        self.bids.push(amount);
-        self.state.0 = self.state.0.next();
+        let turn = self.state.turn;
+        let layout = self.layout();
 
-        if self.bids.len() < NUMBER_OF_PLAYERS {
-            Either::Left((self, None))
-        } else {
-            let (_, highest_bidder) = self
-                .bids
-                .iter()
-                .zip(self.state.0)
-                .max_by_key(|(bid, _)| *bid)
-                .unwrap();
-
-            Either::Right(Active {
-                scores: self.scores,
-                initial_bidder: self.initial_bidder,
-                bids: self.bids,
-                hands: self.hands,
-                state: SelectingTrumpState(highest_bidder),
-            })
+        self.bids.push((turn, amount.map(Bid::Points)));
+
+        let still_in: Vec<Player> = layout
+            .starting_from(layout.next(turn))
+            .filter(|p| !self.has_passed(*p))
+            .collect();
+
+        match (&still_in[..], self.winning_bid()) {
+            ([], _) => {
+                // everyone passed, so throw the cards in
+                Either::Left((self.deal(), None))
+            }
+            ([_], Some((highest_bidder, _))) => Either::Right(self.win_auction(highest_bidder)),
+            ([next, ..], _) => {
+                self.state.turn = *next;
+                Either::Left((self, None))
+            }
         }
     }
+
+    /// Gives the widow to the winner of the auction, who names trump
+    fn win_auction(mut self, bidder: Player) -> SelectingTrump {
+        let widow = std::mem::take(&mut self.widow);
+        self.hand_mut(bidder).extend(widow);
+        self.with_state(SelectingTrumpState(bidder))
+    }
 }
 
 impl SelectingTrump {
@@ -235,25 +745,26 @@ impl SelectingTrump {
         self.state.0
     }
 
-    pub fn select(self, suit: Suit) -> PassingCards {
-        Active {
-            scores: self.scores,
-            bids: self.bids,
-            hands: self.hands,
-            initial_bidder: self.initial_bidder,
-            state: PassingCardsState {
-                turn: self.state.0.teammate(),
+    /// Names trump. The bidder's partner then passes cards to them, or if
+    /// they don't have a partner they discard the cards they picked up with
+    /// the widow.
+    pub fn select(self, suit: Suit) -> Either<PassingCards, Discarding> {
+        let bidder = self.state.0;
+        match self.layout().partner(bidder) {
+            Some(turn) => Either::Left(self.with_state(PassingCardsState { turn, trump: suit })),
+            None => Either::Right(self.with_state(DiscardingState {
+                turn: bidder,
                 trump: suit,
-            },
+            })),
         }
     }
 }
 
-fn remove_item<T, F>(vec: &mut Vec<T>, mut f: F) -> bool
+fn remove_item<T, F>(vec: &mut Vec<T>, f: F) -> bool
 where
     F: FnMut(&T) -> bool,
 {
-    if let Some((index, _)) = vec.iter().enumerate().filter(|(_, c)| f(c)).next() {
+    if let Some(index) = vec.iter().position(f) {
         vec.remove(index);
         true
     } else {
@@ -261,27 +772,34 @@ where
     }
 }
 
+type Hand = Vec<Option<Card>>;
+
 fn pass_cards(
-    mut hand1: Vec<Option<Card>>,
-    mut hand2: Vec<Option<Card>>,
-    cards: Option<[Card; 4]>,
-) -> Result<(Vec<Option<Card>>, Vec<Option<Card>>), String> {
-    if let Some(cards) = cards {
+    mut hand1: Hand,
+    mut hand2: Hand,
+    cards: Option<Vec<Card>>,
+    count: usize,
+) -> Result<(Hand, Hand), GameError> {
+    if let Some(cards) = &cards {
+        if cards.len() != count {
+            return Err(GameError::WrongNumberOfCards { expected: count });
+        }
+
         for card in cards.iter() {
             // first try to remove the known card from hand
             // if the known card is not in hand, try to remove some
             // unknown card
-            if !remove_item(&mut hand1, |c| c.map_or(false, |c| &c == card))
+            if !remove_item(&mut hand1, |c| c.as_ref() == Some(card))
                 && !remove_item(&mut hand1, |c| c.is_none())
             {
-                return Err("Card not in hand to pass".to_owned());
+                return Err(GameError::CardNotInHand(*card));
             }
         }
     } else {
-        // if the passed cards aren't known, remove 4 unknown cards from the hand
-        for _ in 0..4 {
+        // if the passed cards aren't known, remove that many unknown cards from the hand
+        for _ in 0..count {
             if !remove_item(&mut hand1, |c| c.is_none()) {
-                return Err("Card not in hand to pass".to_owned());
+                return Err(GameError::CardsNotKnown);
             }
         }
     }
@@ -290,7 +808,7 @@ fn pass_cards(
     if let Some(cards) = cards {
         hand2.extend(cards.iter().map(|x| Some(*x)));
     } else {
-        hand2.extend(iter::repeat(None).take(4));
+        hand2.extend(iter::repeat_n(None, count));
     }
 
     Ok((hand1, hand2))
@@ -305,9 +823,9 @@ impl PassingCards {
         self.state.trump
     }
 
-    pub fn pass(self, cards: Option<[Card; 4]>) -> Result<ReturningCards, String> {
+    pub fn pass(self, cards: Option<Vec<Card>>) -> Result<ReturningCards, GameError> {
         let src = self.turn();
-        let dst = src.teammate();
+        let dst = self.layout().partner(src).unwrap();
 
         let mut hands = self.hands;
 
@@ -315,20 +833,16 @@ impl PassingCards {
             hands.get_value(src).clone(),
             hands.get_value(dst).clone(),
             cards,
+            self.rules.pass_count,
         )?;
         *hands.get_value_mut(src) = new_src;
         *hands.get_value_mut(dst) = new_dst;
 
-        Ok(Active {
-            scores: self.scores,
-            bids: self.bids,
-            hands: hands,
-            initial_bidder: self.initial_bidder,
-            state: ReturningCardsState {
-                turn: self.state.turn.teammate(),
-                trump: self.state.trump,
-            },
-        })
+        let state = ReturningCardsState {
+            turn: dst,
+            trump: self.state.trump,
+        };
+        Ok(Active { hands, ..self }.with_state(state))
     }
 }
 
@@ -341,9 +855,9 @@ impl ReturningCards {
         self.state.trump
     }
 
-    pub fn pass(self, cards: Option<[Card; 4]>) -> Result<Playing, String> {
+    pub fn pass(self, cards: Option<Vec<Card>>) -> Result<Melding, GameError> {
         let src = self.turn();
-        let dst = src.teammate();
+        let dst = self.layout().partner(src).unwrap();
 
         let mut hands = self.hands;
 
@@ -351,22 +865,95 @@ impl ReturningCards {
             hands.get_value(src).clone(),
             hands.get_value(dst).clone(),
             cards,
+            self.rules.pass_count,
         )?;
         *hands.get_value_mut(src) = new_src;
         *hands.get_value_mut(dst) = new_dst;
 
+        let trump = self.state.trump;
+        let meld = find_all_meld(&hands, trump);
+        let state = MeldingState { turn: src, trump };
         Ok(Active {
-            scores: self.scores,
-            bids: self.bids,
-            hands: hands,
-            initial_bidder: self.initial_bidder,
-            state: PlayingState {
-                turn: self.state.turn,
-                play_area: Vec::new(),
-                taken: [Vec::new(), Vec::new()],
-                trump: self.state.trump,
-            },
-        })
+            hands,
+            meld,
+            ..self
+        }
+        .with_state(state))
+    }
+}
+
+impl Discarding {
+    pub fn turn(&self) -> Player {
+        self.state.turn
+    }
+
+    pub fn trump(&self) -> Suit {
+        self.state.trump
+    }
+
+    /// Sets aside as many cards as there were in the widow. They count
+    /// towards the bidder's counters at the end of the round.
+    pub fn discard(self, cards: Option<Vec<Card>>) -> Result<Melding, GameError> {
+        let bidder = self.turn();
+
+        let mut hands = self.hands;
+
+        let (new_hand, widow) = pass_cards(
+            hands.get_value(bidder).clone(),
+            Vec::new(),
+            cards,
+            self.rules.widow,
+        )?;
+        *hands.get_value_mut(bidder) = new_hand;
+
+        let trump = self.state.trump;
+        let meld = find_all_meld(&hands, trump);
+        let state = MeldingState {
+            turn: bidder,
+            trump,
+        };
+        Ok(Active {
+            hands,
+            widow,
+            meld,
+            ..self
+        }
+        .with_state(state))
+    }
+}
+
+/// The meld in each hand, as far as the cards in it are known
+fn find_all_meld(hands: &PlayerMap<Hand>, trump: Suit) -> PlayerMap<Vec<Meld>> {
+    hands.map(|_, hand| {
+        let hand: Vec<Card> = hand.iter().filter_map(|c| *c).collect();
+        meld::find_meld(&hand, trump)
+    })
+}
+
+impl Melding {
+    pub fn turn(&self) -> Player {
+        self.state.turn
+    }
+
+    pub fn trump(&self) -> Suit {
+        self.state.trump
+    }
+
+    /// Concedes the hand for the bidder once they've seen the meld
+    pub fn throw_in(self) -> FinishedRound {
+        let trump = self.state.trump;
+        self.concede(trump)
+    }
+
+    pub fn next(self) -> Playing {
+        let state = PlayingState {
+            turn: self.state.turn,
+            play_area: Vec::new(),
+            taken: vec![Vec::new(); self.layout().number_of_teams()],
+            tricks: Vec::new(),
+            trump: self.state.trump,
+        };
+        self.with_state(state)
     }
 }
 
@@ -383,11 +970,39 @@ impl Playing {
         self.state.taken[team as usize].clone()
     }
 
+    /// Every trick played so far this round, in order
+    pub fn tricks(&self) -> &[Trick] {
+        &self.state.tricks
+    }
+
+    pub fn last_trick(&self) -> Option<&Trick> {
+        self.state.tricks.last()
+    }
+
     pub fn turn(&self) -> Player {
         self.state.turn
     }
 
-    pub fn play(mut self, card: Card) -> Either<(Playing, Option<&'static str>), FinishedRound> {
+    /// Whether the player whose turn it is can throw in: they won the
+    /// auction, and no card has been played yet
+    pub fn check_throw_in(&self) -> Result<(), GameError> {
+        if !self.state.tricks.is_empty() || !self.state.play_area.is_empty() {
+            return Err(GameError::TooLateToThrowIn);
+        }
+        match self.winning_bid() {
+            Some((bidder, _)) if bidder == self.state.turn => Ok(()),
+            _ => Err(GameError::NotBidder),
+        }
+    }
+
+    /// Concedes the hand for the bidder before they lead
+    pub fn throw_in(self) -> Result<FinishedRound, GameError> {
+        self.check_throw_in()?;
+        let trump = self.state.trump;
+        Ok(self.concede(trump))
+    }
+
+    pub fn play(mut self, card: Card) -> Either<(Playing, Option<GameError>), FinishedRound> {
         let hand = self.hand(self.state.turn);
 
         match is_legal(&self.state.play_area, hand, &card, self.state.trump) {
@@ -395,56 +1010,85 @@ impl Playing {
             Err(x) => return Either::Left((self, Some(x))),
         }
 
-        if let Some(position) = hand.iter().position(|&x| x.map_or(false, |x| x == card)) {
+        if let Some(position) = hand.iter().position(|&x| x == Some(card)) {
             self.hand_mut(self.state.turn).remove(position);
         } else if let Some(position) = hand.iter().position(|&x| x.is_none()) {
             self.hand_mut(self.state.turn).remove(position);
         } else {
-            return Either::Left((self, Some("Card not in hand")));
+            return Either::Left((self, Some(GameError::CardNotInHand(card))));
         }
+        let layout = self.layout();
         self.state.play_area.push(card);
-        self.state.turn = self.state.turn.next();
-
-        if self.state.play_area.len() == NUMBER_OF_PLAYERS {
-            let first_player = self.state.turn;
-            let led_suit = self.state.play_area[0].suit;
-
-            // regular rust max_by returns the last winner when
-            // deciding ties. This returns the first
-            let mut iter = self.state.play_area.iter().zip(first_player);
-            let (mut card, mut winner) = iter.next().unwrap();
-            for (c, p) in iter {
-                if compare_cards(c, card, &led_suit, &self.state.trump) == Ordering::Greater {
-                    card = c;
-                    winner = p;
-                }
-            }
+        self.state.turn = layout.next(self.state.turn);
 
-            self.state.taken[winner.team() as usize].extend(self.state.play_area.iter());
-            self.state.play_area.clear();
+        if self.state.play_area.len() == layout.number_of_players() {
+            let leader = self.state.turn;
+            let trick = collect_trick(&mut self.state.play_area, leader, self.state.trump, layout);
 
-            self.state.turn = winner;
+            self.state.taken[layout.team(trick.winner) as usize].extend(trick.cards.iter());
+            self.state.turn = trick.winner;
+            self.state.tricks.push(trick);
 
-            if self.hand(Player::A).len() == 0 {
+            if self.hand(Player::A).is_empty() {
                 return Either::Right(self.calculate_score());
             }
         }
-        return Either::Left((self, None));
+        Either::Left((self, None))
     }
 
     fn calculate_score(mut self) -> FinishedRound {
-        *self.score_mut(Team::Red) += 1;
+        let layout = self.layout();
+        let last_trick = self.last_trick().map(|t| layout.team(t.winner));
+        let mut counters = vec![0; layout.number_of_teams()];
+        let mut round_scores = vec![0; layout.number_of_teams()];
+
+        // there's no bid in a stock game
+        let winning_bid = self.winning_bid();
+        if let Some((bidder, _)) = winning_bid {
+            let discards = std::mem::take(&mut self.widow);
+            self.state.taken[layout.team(bidder) as usize].extend(discards.into_iter().flatten());
+        }
 
-        Active {
-            hands: self.hands,
-            initial_bidder: self.initial_bidder,
-            scores: self.scores,
-            bids: self.bids,
-            state: FinishedRoundState {
-                taken: self.state.taken,
-                trump: self.state.trump,
-            },
+        for team in layout.teams() {
+            counters[team as usize] = count_counters(&self.state.taken[team as usize]);
+            if Some(team) == last_trick {
+                counters[team as usize] += LAST_TRICK_BONUS;
+            }
+
+            let points = self.team_meld(team) + counters[team as usize];
+            let every_trick = self
+                .state
+                .tricks
+                .iter()
+                .all(|t| layout.team(t.winner) == team);
+            round_scores[team as usize] = match winning_bid {
+                Some((bidder, bid))
+                    if team == layout.team(bidder) && self.moon_bidder().is_some() =>
+                {
+                    if every_trick {
+                        bid as isize
+                    } else {
+                        -(bid as isize)
+                    }
+                }
+                Some((bidder, bid)) if team == layout.team(bidder) && points < bid => {
+                    -(bid as isize)
+                }
+                _ => points as isize,
+            };
+            *self.score_mut(team) += round_scores[team as usize];
         }
+        self.rounds += 1;
+
+        let state = FinishedRoundState {
+            taken: std::mem::take(&mut self.state.taken),
+            tricks: std::mem::take(&mut self.state.tricks),
+            trump: self.state.trump,
+            counters,
+            round_scores,
+            thrown_in: false,
+        };
+        self.with_state(state)
     }
 }
 
@@ -457,32 +1101,361 @@ impl FinishedRound {
         self.state.taken[team as usize].clone()
     }
 
-    pub fn next(mut self) -> Either<Bidding, Finished> {
-        self.bids.clear();
+    /// Every trick played in the round, in order
+    pub fn tricks(&self) -> &[Trick] {
+        &self.state.tricks
+    }
 
-        if *self.scores.iter().max().unwrap() > 2000 {
-            Either::Right(Finished(self.scores))
-        } else {
-            let initial_bidder = self.initial_bidder.next();
-            Either::Left(Active {
-                initial_bidder: initial_bidder,
-                hands: hands_to_option(shuffle()),
-                bids: self.bids,
-                scores: self.scores,
-                state: BiddingState(initial_bidder),
-            })
-        }
+    pub fn last_trick(&self) -> Option<&Trick> {
+        self.state.tricks.last()
     }
-}
 
-pub fn hands_to_option(hands: PlayerMap<Vec<Card>>) -> PlayerMap<Vec<Option<Card>>> {
-    hands.map_move(|_, x| x.iter().map(|a| Some(*a)).collect())
-}
+    pub fn counters(&self, team: Team) -> usize {
+        self.state.counters[team as usize]
+    }
+
+    pub fn round_score(&self, team: Team) -> isize {
+        self.state.round_scores[team as usize]
+    }
+
+    /// Every card, including a hand that was thrown in, for going over the
+    /// hand once it's done. The dealer stays hidden, so later deals can't
+    /// be worked out.
+    pub fn review(&self) -> FinishedRound {
+        FinishedRound {
+            hands: self.hands.clone(),
+            widow: self.widow.clone(),
+            ..self.project_for(Viewer::Spectator)
+        }
+    }
+
+    /// Whether the bidder conceded the hand instead of playing it
+    pub fn thrown_in(&self) -> bool {
+        self.state.thrown_in
+    }
+
+    /// The team that shot the moon and took every trick
+    pub fn made_the_moon(&self) -> Option<Team> {
+        let layout = self.layout();
+        let team = layout.team(self.moon_bidder()?);
+        let every_trick = self
+            .state
+            .tricks
+            .iter()
+            .all(|t| layout.team(t.winner) == team);
+        if every_trick && !self.state.thrown_in {
+            Some(team)
+        } else {
+            None
+        }
+    }
+
+    pub fn next(self) -> Either<Either<Bidding, Stock>, Finished> {
+        let layout = self.layout();
+        let winner = layout.teams().max_by_key(|t| self.score(*t)).unwrap();
+        let tied = layout
+            .teams()
+            .any(|t| t != winner && self.score(t) == self.score(winner));
+
+        let moon = self.made_the_moon().filter(|_| self.rules.moon_wins_game);
+
+        if let Some(winner) = moon {
+            Either::Right(self.finish(winner))
+        } else if self.score(winner) > self.rules.target_score && !tied {
+            Either::Right(self.finish(winner))
+        } else {
+            Either::Left(self.next_deal())
+        }
+    }
+
+    fn finish(self, winner: Team) -> Finished {
+        Finished {
+            scores: self.scores,
+            winner,
+            rounds: self.rounds,
+            rules: self.rules,
+            dealer: self.dealer,
+        }
+    }
+}
+
+impl Bidding {
+    /// Turns up the last card of the stock for trump, and starts play with
+    /// the initial bidder leading. The dealer melds the dix if it's turned
+    /// up.
+    fn turn_up_trump(mut self) -> Stock {
+        let leader = self.initial_bidder();
+        let trump_card = self.widow.pop().flatten();
+        if trump_card.is_some_and(|c| c.rank == Rank::Nine) {
+            self.meld.get_value_mut(self.dealer_seat).push(Meld::Dix);
+        }
+
+        let state = StockState {
+            play_area: Vec::new(),
+            taken: vec![Vec::new(); self.layout().number_of_teams()],
+            tricks: Vec::new(),
+            // only a hidden dealer turns up an unknown card
+            trump: trump_card.map_or(Suit::Heart, |c| c.suit),
+            trump_card,
+            shown: PlayerMap::from_fn(self.layout(), |_| Vec::new()),
+            turn: leader,
+        };
+        self.with_state(state)
+    }
+}
+
+impl Stock {
+    /// Starts a two handed game with every deal made by `dealer`, and
+    /// `first_player` leading to the first trick
+    pub fn with_dealer(first_player: Player, dealer: Dealer, rules: RuleSet) -> Stock {
+        Bidding::with_dealer(first_player, dealer, rules).turn_up_trump()
+    }
+
+    pub fn turn(&self) -> Player {
+        self.state.turn
+    }
+
+    pub fn trump(&self) -> Suit {
+        self.state.trump
+    }
+
+    pub fn trump_card(&self) -> Option<Card> {
+        self.state.trump_card
+    }
+
+    pub fn play_area(&self) -> &[Card] {
+        &self.state.play_area
+    }
+
+    pub fn taken(&self, team: Team) -> Vec<Card> {
+        self.state.taken[team as usize].clone()
+    }
+
+    pub fn tricks(&self) -> &[Trick] {
+        &self.state.tricks
+    }
+
+    /// The cards left to draw, including the trump card
+    pub fn stock_size(&self) -> usize {
+        self.widow.len() + self.state.trump_card.iter().count()
+    }
+
+    pub fn shown(&self, p: Player) -> &[(Card, Vec<Meld>)] {
+        self.state.shown.get_value(p)
+    }
+
+    /// Plays any card from the hand. Once both players have played, the
+    /// winner of the trick may declare meld.
+    pub fn play(mut self, card: Card) -> Either<(Stock, Option<GameError>), Declaring> {
+        let turn = self.state.turn;
+        let hand = self.hand(turn);
+
+        if let Some(position) = hand.iter().position(|&x| x == Some(card)) {
+            self.hand_mut(turn).remove(position);
+        } else if let Some(position) = hand.iter().position(|&x| x.is_none()) {
+            self.hand_mut(turn).remove(position);
+        } else {
+            return Either::Left((self, Some(GameError::CardNotInHand(card))));
+        }
+        remove_item(self.state.shown.get_value_mut(turn), |(c, _)| *c == card);
+
+        let layout = self.layout();
+        self.state.play_area.push(card);
+        self.state.turn = layout.next(turn);
+
+        if self.state.play_area.len() == layout.number_of_players() {
+            let leader = self.state.turn;
+            let trick = collect_trick(&mut self.state.play_area, leader, self.state.trump, layout);
+
+            self.state.taken[layout.team(trick.winner) as usize].extend(trick.cards.iter());
+            self.state.turn = trick.winner;
+            self.state.tricks.push(trick);
+
+            let state = DeclaringState(self.state.clone());
+            return Either::Right(self.with_state(state));
+        }
+        Either::Left((self, None))
+    }
+}
+
+impl Declaring {
+    pub fn turn(&self) -> Player {
+        self.state.0.turn
+    }
+
+    pub fn trump(&self) -> Suit {
+        self.state.0.trump
+    }
+
+    pub fn trump_card(&self) -> Option<Card> {
+        self.state.0.trump_card
+    }
+
+    pub fn taken(&self, team: Team) -> Vec<Card> {
+        self.state.0.taken[team as usize].clone()
+    }
+
+    /// The trick just won by the player declaring
+    pub fn last_trick(&self) -> &Trick {
+        self.state.0.tricks.last().expect("a trick has been played")
+    }
+
+    pub fn stock_size(&self) -> usize {
+        self.widow.len() + self.state.0.trump_card.iter().count()
+    }
+
+    pub fn shown(&self, p: Player) -> &[(Card, Vec<Meld>)] {
+        self.state.0.shown.get_value(p)
+    }
+
+    /// Every meld the player could declare now
+    pub fn declarations(&self) -> Vec<Meld> {
+        let hand: Vec<Card> = self.hand(self.turn()).iter().filter_map(|c| *c).collect();
+        let mut declarations: Vec<Meld> = Vec::new();
+        for found in meld::find_meld(&hand, self.trump()) {
+            let lesser = match found {
+                Meld::Run(n) => (1..=n).map(Meld::Run).collect(),
+                Meld::Pinochle(n) => (1..=n).map(Meld::Pinochle).collect(),
+                Meld::Around(rank, n) => (1..=n).map(|n| Meld::Around(rank, n)).collect(),
+                meld => vec![meld],
+            };
+            for meld in lesser {
+                if !declarations.contains(&meld) && self.check_declaration(meld).is_ok() {
+                    declarations.push(meld);
+                }
+            }
+        }
+        declarations
+    }
+
+    /// A meld must be made from cards in hand, including at least one that
+    /// isn't already on the table. Cards on the table can be used again,
+    /// but only in a different kind of meld.
+    fn check_declaration(&self, meld: Meld) -> Result<(), GameError> {
+        let hand = self.hand(self.turn());
+        let shown = self.shown(self.turn());
+        let mut concealed = false;
+
+        for card in meld.cards(self.trump()).iter() {
+            let needed = meld
+                .cards(self.trump())
+                .iter()
+                .filter(|c| *c == card)
+                .count();
+            let held = hand.iter().filter(|c| **c == Some(*card)).count();
+            let on_table = shown.iter().filter(|(c, _)| c == card).count();
+            let used = shown
+                .iter()
+                .filter(|(c, melds)| c == card && melds.iter().any(|m| m.same_kind(&meld)))
+                .count();
+
+            if held < needed + used {
+                return Err(GameError::MeldNotInHand(meld));
+            }
+            if held > on_table {
+                concealed = true;
+            }
+        }
+
+        if concealed {
+            Ok(())
+        } else {
+            Err(GameError::MeldAlreadyShown(meld))
+        }
+    }
+
+    /// Declares `meld`, or nothing, and then both players draw. Melding the
+    /// dix exchanges it for the trump card. When the stock runs out, the
+    /// last tricks are played under the usual rules.
+    pub fn declare(mut self, meld: Option<Meld>) -> Result<Either<Stock, Playing>, GameError> {
+        let winner = self.turn();
+        let trump = self.trump();
+
+        if let Some(meld) = meld {
+            self.check_declaration(meld)?;
+            if !self.declarations().contains(&meld) {
+                return Err(GameError::NotAMeld(meld));
+            }
+            self.meld.get_value_mut(winner).push(meld);
+
+            let dix = Card {
+                suit: trump,
+                rank: Rank::Nine,
+            };
+            match (meld, self.state.0.trump_card) {
+                (Meld::Dix, Some(trump_card)) => {
+                    let hand = self.hand_mut(winner);
+                    if !remove_item(hand, |c| *c == Some(dix)) {
+                        remove_item(hand, |c| c.is_none());
+                    }
+                    hand.push(Some(trump_card));
+                    self.state.0.trump_card = Some(dix);
+                }
+                _ => {
+                    // lay down cards from the hand first, then reuse ones on
+                    // the table
+                    for card in meld.cards(trump) {
+                        let held = self
+                            .hand(winner)
+                            .iter()
+                            .filter(|c| **c == Some(card))
+                            .count();
+                        let shown = self.state.0.shown.get_value_mut(winner);
+                        if held > shown.iter().filter(|(c, _)| *c == card).count() {
+                            shown.push((card, vec![meld]));
+                        } else if let Some((_, melds)) = shown.iter_mut().find(|(c, melds)| {
+                            *c == card && !melds.iter().any(|m| m.same_kind(&meld))
+                        }) {
+                            melds.push(meld);
+                        }
+                    }
+                }
+            }
+        }
+
+        let loser = self.layout().next(winner);
+        let drawn = self.widow.pop();
+        self.hand_mut(winner).extend(drawn);
+        let drawn = match self.widow.pop() {
+            Some(card) => Some(card),
+            None => self.state.0.trump_card.take().map(Some),
+        };
+        self.hand_mut(loser).extend(drawn);
+
+        let DeclaringState(state) = self.state.clone();
+        if self.widow.is_empty() && state.trump_card.is_none() {
+            // the melded cards are picked back up for the last tricks
+            Ok(Either::Right(self.with_state(PlayingState {
+                play_area: Vec::new(),
+                taken: state.taken,
+                tricks: state.tricks,
+                trump: state.trump,
+                turn: winner,
+            })))
+        } else {
+            Ok(Either::Left(self.with_state(state)))
+        }
+    }
+}
+
+pub(crate) const LAST_TRICK_BONUS: usize = 10;
+
+/// Aces, tens and kings are the counters
+pub(crate) fn is_counter(card: &Card) -> bool {
+    card.rank == Rank::Ace || card.rank == Rank::Ten || card.rank == Rank::King
+}
+
+/// Counters are worth 10 points each, everything else is worth nothing.
+pub(crate) fn count_counters(cards: &[Card]) -> usize {
+    cards.iter().filter(|c| is_counter(c)).count() * 10
+}
+
+pub fn hands_to_option(hands: PlayerMap<Vec<Card>>) -> PlayerMap<Vec<Option<Card>>> {
+    hands.map_move(|_, x| x.iter().map(|a| Some(*a)).collect())
+}
 
 fn has_suit(hand: &[Option<Card>], suit: Suit) -> bool {
-    hand.iter()
-        .map(|&card| card.map_or(false, |c| c.suit == suit))
-        .any(|b| b)
+    hand.iter().any(|card| card.is_some_and(|c| c.suit == suit))
 }
 
 pub fn is_legal(
@@ -490,30 +1463,51 @@ pub fn is_legal(
     hand: &[Option<Card>],
     card: &Card,
     trump: Suit,
-) -> Result<(), &'static str> {
-    if play_area.len() == 0 {
-        Result::Ok(())
+) -> Result<(), GameError> {
+    if play_area.is_empty() {
+        return Result::Ok(());
+    }
+
+    let led_suit = play_area[0].suit;
+    let winning = &play_area[winning_index(play_area, trump)];
+    let beats = |c: &Card| compare_cards(c, winning, &led_suit, &trump) == Ordering::Greater;
+    let can_beat = |suit: Suit| hand.iter().flatten().any(|c| c.suit == suit && beats(c));
+
+    let must_play = if has_suit(hand, led_suit) {
+        led_suit
+    } else if has_suit(hand, trump) {
+        trump
     } else {
-        let intial_suit = play_area[0].suit;
+        return Result::Ok(());
+    };
 
-        if has_suit(hand, intial_suit) {
-            if intial_suit == card.suit {
-                Result::Ok(())
-            } else {
-                Result::Err("Must follow suit")
-            }
+    if card.suit != must_play {
+        if must_play == led_suit {
+            Result::Err(GameError::MustFollowSuit(led_suit))
         } else {
-            if has_suit(hand, trump) {
-                if trump == card.suit {
-                    Result::Ok(())
-                } else {
-                    Result::Err("Must trump")
-                }
-            } else {
-                Result::Ok(())
-            }
+            Result::Err(GameError::MustTrump)
+        }
+    } else if can_beat(must_play) && !beats(card) {
+        Result::Err(GameError::MustBeat(*winning))
+    } else {
+        Result::Ok(())
+    }
+}
+
+/// The index of the card currently winning the trick. When two cards tie
+/// the first one played wins.
+pub fn winning_index(play_area: &[Card], trump: Suit) -> usize {
+    let led_suit = play_area[0].suit;
+
+    // regular rust max_by returns the last winner when
+    // deciding ties. This returns the first
+    let mut winner = 0;
+    for (i, c) in play_area.iter().enumerate().skip(1) {
+        if compare_cards(c, &play_area[winner], &led_suit, &trump) == Ordering::Greater {
+            winner = i;
         }
     }
+    winner
 }
 
 fn compare_cards(c1: &Card, c2: &Card, led_suit: &Suit, trump_suit: &Suit) -> Ordering {
@@ -543,15 +1537,32 @@ fn compare_cards(c1: &Card, c2: &Card, led_suit: &Suit, trump_suit: &Suit) -> Or
 
 #[cfg(test)]
 mod test {
+    use super::super::{dealer, notation};
     use super::*;
 
     const HX: Card = Card {
         suit: Suit::Heart,
         rank: Rank::Ten,
     };
+    const QS: Card = Card {
+        suit: Suit::Spade,
+        rank: Rank::Queen,
+    };
+    const JD: Card = Card {
+        suit: Suit::Diamond,
+        rank: Rank::Jack,
+    };
+    const AH: Card = Card {
+        suit: Suit::Heart,
+        rank: Rank::Ace,
+    };
+    const C9: Card = Card {
+        suit: Suit::Club,
+        rank: Rank::Nine,
+    };
 
     #[test]
-    fn simple_round() -> Result<(), String> {
+    fn simple_round() -> Result<(), GameError> {
         let game = Bidding::new(
             Player::A,
             PlayerMap::new(
@@ -564,13 +1575,16 @@ mod test {
         let (game, _) = game.bid(250).left().unwrap();
         let (game, _) = game.pass().left().unwrap();
         let (game, _) = game.bid(300).left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
         let game = game.pass().right().unwrap();
         assert_eq!(game.turn(), Player::C);
-        let game = game.select(Suit::Heart);
+        let game = game.select(Suit::Heart).left().unwrap();
         assert_eq!(game.turn(), Player::A);
-        let game = game.pass(Some([HX, HX, HX, HX]))?;
+        let game = game.pass(Some(vec![HX, HX, HX, HX]))?;
         assert_eq!(game.turn(), Player::C);
-        let game = game.pass(Some([HX, HX, HX, HX]))?;
+        let game = game.pass(Some(vec![HX, HX, HX, HX]))?;
+        assert_eq!(game.turn(), Player::C);
+        let game = game.next();
         assert_eq!(game.turn(), Player::C);
         let (game, _) = game.play(HX).left().unwrap();
         let (game, _) = game.play(HX).left().unwrap();
@@ -595,9 +1609,591 @@ mod test {
                 vec![],
             ]
         );
+        assert_eq!(game.counters(Team::Red), 170);
+        assert_eq!(game.counters(Team::Blue), 0);
+        // 170 doesn't make the bid of 300
+        assert_eq!(game.round_score(Team::Red), -300);
+        assert_eq!(game.score(Team::Red), -300);
+        assert_eq!(game.score(Team::Blue), 0);
         let game = game.next();
         game.left().unwrap();
 
         Ok(())
     }
+
+    #[test]
+    fn scores_meld_and_counters() -> Result<(), GameError> {
+        let game = Bidding::new(
+            Player::A,
+            hands_to_option(PlayerMap::new(
+                vec![QS, QS, JD, JD],
+                vec![C9, C9, C9, C9],
+                vec![AH, AH, AH, AH],
+                vec![C9, C9, C9, C9],
+            )),
+        );
+        let (game, _) = game.bid(250).left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let game = game.pass().right().unwrap();
+        let game = game.select(Suit::Heart).left().unwrap();
+        let game = game.pass(Some(vec![AH, AH, AH, AH]))?;
+        let game = game.pass(Some(vec![AH, AH, AH, AH]))?;
+        assert_eq!(game.meld(Player::A), &[Meld::Pinochle(2)]);
+        assert_eq!(game.team_meld(Team::Red), 300);
+
+        let mut game = game.next();
+        for card in &[QS, C9, AH, C9, AH, C9, QS, C9, AH, C9, JD, C9, AH, C9, JD] {
+            game = game.play(*card).left().unwrap().0;
+        }
+        let game = game.play(C9).right().unwrap();
+
+        assert_eq!(game.tricks().len(), 4);
+        assert_eq!(
+            game.tricks()[0],
+            Trick {
+                leader: Player::A,
+                cards: vec![QS, C9, AH, C9],
+                winner: Player::C,
+            }
+        );
+        let last = game.last_trick().unwrap();
+        assert_eq!(last.leader, Player::C);
+        assert_eq!(last.winner, Player::C);
+        assert_eq!(last.card(Player::A, Layout::PARTNERSHIP), Some(JD));
+
+        assert_eq!(game.counters(Team::Red), 50);
+        assert_eq!(game.round_score(Team::Red), 350);
+        assert_eq!(game.score(Team::Red), 350);
+        assert_eq!(game.score(Team::Blue), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn throw_in() -> Result<(), GameError> {
+        let game = Bidding::new(
+            Player::A,
+            hands_to_option(PlayerMap::new(
+                vec![QS, QS, JD, JD],
+                vec![C9, C9, C9, C9],
+                vec![AH, AH, AH, AH],
+                vec![C9, C9, C9, C9],
+            )),
+        );
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.bid(250).left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let game = game.pass().right().unwrap();
+        let game = game.select(Suit::Heart).left().unwrap();
+        let game = game.pass(Some(vec![C9, C9, C9, C9]))?;
+        let game = game.pass(Some(vec![C9, C9, C9, C9]))?;
+        assert_eq!(game.turn(), Player::B);
+
+        let playing = game.clone().next();
+        assert_eq!(playing.check_throw_in(), Ok(()));
+        let (playing, _) = playing.play(C9).left().unwrap();
+        assert_eq!(playing.throw_in(), Err(GameError::TooLateToThrowIn));
+
+        // the bidders lose the bid, and their opponents keep their meld
+        let game = game.throw_in();
+        assert!(game.thrown_in());
+        assert!(game.tricks().is_empty());
+        assert_eq!(game.round_score(Team::Blue), -250);
+        assert_eq!(game.round_score(Team::Red), 300);
+        assert_eq!(game.score(Team::Red), 300);
+        assert_eq!(game.rounds(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn shoot_the_moon() -> Result<(), GameError> {
+        let rules = RuleSet {
+            shoot_the_moon: Some(1000),
+            moon_wins_game: true,
+            ..RuleSet::default()
+        };
+        let play =
+            |blue: Card, trump: Suit, cards: [Card; 16]| -> Result<FinishedRound, GameError> {
+                let game = Bidding::with_rules(
+                    Player::A,
+                    hands_to_option(PlayerMap::new(
+                        vec![QS, QS, JD, JD],
+                        vec![blue; 4],
+                        vec![C9, C9, C9, C9],
+                        vec![C9, C9, C9, C9],
+                    )),
+                    rules.clone(),
+                );
+                let game = game.shoot_the_moon().right().unwrap();
+                assert_eq!(game.bids(), &[(Player::A, Some(Bid::Moon))]);
+                assert_eq!(game.winning_bid(), Some((Player::A, 1000)));
+                let game = game.select(trump).left().unwrap();
+                let game = game.pass(Some(vec![C9, C9, C9, C9]))?;
+                let game = game.pass(Some(vec![C9, C9, C9, C9]))?;
+
+                let mut game = game.next();
+                for card in &cards[..15] {
+                    let (next, err) = game.play(*card).left().unwrap();
+                    assert_eq!(err, None);
+                    game = next;
+                }
+                Ok(game.play(cards[15]).right().unwrap())
+            };
+
+        // the ace of hearts trumps a spade lead, and leads from then on
+        let missed = play(
+            AH,
+            Suit::Heart,
+            [
+                QS, AH, C9, C9, AH, C9, C9, QS, AH, C9, C9, JD, AH, C9, C9, JD,
+            ],
+        )?;
+        assert_eq!(missed.made_the_moon(), None);
+        assert_eq!(missed.round_score(Team::Red), -1000);
+        assert!(missed.next().is_left());
+
+        let made = play(
+            C9,
+            Suit::Spade,
+            [
+                QS, C9, C9, C9, QS, C9, C9, C9, JD, C9, C9, C9, JD, C9, C9, C9,
+            ],
+        )?;
+        assert_eq!(made.made_the_moon(), Some(Team::Red));
+        assert_eq!(made.round_score(Team::Red), 1000);
+        assert_eq!(made.next().right().unwrap().winner(), Team::Red);
+
+        let game = Bidding::new(
+            Player::A,
+            hands_to_option(PlayerMap::new(vec![], vec![], vec![], vec![])),
+        );
+        let (_, err) = game.shoot_the_moon().left().unwrap();
+        assert_eq!(err, Some(GameError::MoonNotAllowed));
+
+        Ok(())
+    }
+
+    #[test]
+    fn bidder_goes_set() -> Result<(), GameError> {
+        let game = Bidding::new(
+            Player::A,
+            hands_to_option(PlayerMap::new(
+                vec![C9, C9, C9, C9],
+                vec![QS, QS, JD, JD],
+                vec![C9, C9, C9, C9],
+                vec![AH, AH, AH, AH],
+            )),
+        );
+        let (game, _) = game.bid(250).left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let game = game.pass().right().unwrap();
+        let game = game.select(Suit::Heart).left().unwrap();
+        let game = game.pass(Some(vec![C9, C9, C9, C9]))?;
+        let game = game.pass(Some(vec![C9, C9, C9, C9]))?;
+
+        let mut game = game.next();
+        for card in &[C9, QS, C9, AH, AH, C9, QS, C9, AH, C9, JD, C9, AH, C9, JD] {
+            game = game.play(*card).left().unwrap().0;
+        }
+        let game = game.play(C9).right().unwrap();
+
+        assert_eq!(game.round_score(Team::Red), -250);
+        assert_eq!(game.round_score(Team::Blue), 350);
+        assert_eq!(game.score(Team::Red), -250);
+        assert_eq!(game.score(Team::Blue), 350);
+
+        Ok(())
+    }
+
+    #[test]
+    fn must_head_the_trick() {
+        let card = |suit, rank| Card { suit, rank };
+        let legal = |play_area: &[Card], hand: &[Card], c: Card| {
+            let hand: Vec<_> = hand.iter().map(|c| Some(*c)).collect();
+            is_legal(play_area, &hand, &c, Suit::Spade)
+        };
+        let kh = card(Suit::Heart, Rank::King);
+        let qh = card(Suit::Heart, Rank::Queen);
+        let qs = card(Suit::Spade, Rank::Queen);
+        let ks = card(Suit::Spade, Rank::King);
+        let js = card(Suit::Spade, Rank::Jack);
+
+        // following suit
+        assert_eq!(legal(&[kh], &[qh, AH], qh), Err(GameError::MustBeat(kh)));
+        assert!(legal(&[kh], &[qh, AH], AH).is_ok());
+        assert!(legal(&[kh, AH], &[qh, kh], qh).is_ok());
+
+        // following suit can't beat a trick that has been trumped
+        assert!(legal(&[kh, qs], &[qh, AH], qh).is_ok());
+
+        // trumping in has to beat any trump already played
+        assert_eq!(
+            legal(&[kh, qs], &[js, ks], js),
+            Err(GameError::MustBeat(qs))
+        );
+        assert!(legal(&[kh, qs], &[js, ks], ks).is_ok());
+        assert!(legal(&[kh, qs], &[js, C9], js).is_ok());
+        assert_eq!(legal(&[kh, qs], &[js, C9], C9), Err(GameError::MustTrump));
+    }
+
+    #[test]
+    fn finishes_past_2000() {
+        let game = Bidding::new(
+            Player::A,
+            hands_to_option(PlayerMap::new(vec![], vec![], vec![], vec![])),
+        );
+        let state = FinishedRoundState {
+            taken: vec![vec![], vec![]],
+            tricks: vec![],
+            trump: Suit::Heart,
+            counters: vec![0, 0],
+            round_scores: vec![0, 0],
+            thrown_in: false,
+        };
+        let mut game = game.with_state(state.clone());
+        game.rounds = 7;
+
+        // a tie for the lead plays on
+        game.scores = vec![2010, 2010];
+        let mut game = game
+            .next()
+            .left()
+            .unwrap()
+            .left()
+            .unwrap()
+            .with_state(state);
+
+        game.scores = vec![1990, 2010];
+        let finished = game.next().right().unwrap();
+        assert_eq!(finished.winner(), Team::Blue);
+        assert_eq!(finished.score(Team::Red), 1990);
+        assert_eq!(finished.rounds(), 7);
+    }
+
+    #[test]
+    fn house_rules() -> Result<(), GameError> {
+        let rules = RuleSet {
+            deck: Deck::Single,
+            layout: Layout::PARTNERSHIP,
+            widow: 0,
+            stock: false,
+            min_bid: 300,
+            bid_increment: 10,
+            pass_count: 3,
+            target_score: 1000,
+            dealer_stuck: false,
+            redeal_nines: None,
+            redeal_without_meld_or_aces: false,
+            shoot_the_moon: None,
+            moon_wins_game: false,
+        };
+        let hands = hands_to_option(PlayerMap::new(
+            vec![HX, HX, HX],
+            vec![HX, HX, HX],
+            vec![HX, HX, HX],
+            vec![HX, HX, HX],
+        ));
+
+        let game = Bidding::with_rules(Player::A, hands.clone(), rules.clone());
+        let (game, err) = game.bid(250).left().unwrap();
+        assert_eq!(err, Some(GameError::BidTooLow { min: 300 }));
+        let (game, err) = game.bid(305).left().unwrap();
+        assert_eq!(err, Some(GameError::BadIncrement));
+        let (game, _) = game.bid(310).left().unwrap();
+        assert_eq!(game.bids(), &[(Player::A, Some(Bid::Points(310)))]);
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let game = game.pass().right().unwrap();
+        let game = game.select(Suit::Heart).left().unwrap();
+        assert_eq!(
+            game.clone().pass(Some(vec![HX, HX, HX, HX])).err(),
+            Some(GameError::WrongNumberOfCards { expected: 3 })
+        );
+        game.pass(Some(vec![HX, HX, HX]))?;
+
+        // without dealer stuck, everyone passing throws the hand in
+        let game = Bidding::with_rules(Player::A, hands, rules);
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, err) = game.pass().left().unwrap();
+        assert!(err.is_none());
+        assert_eq!(game.deals(), 1);
+        assert_eq!(game.bids(), &[]);
+        assert_eq!(game.turn(), Player::B);
+
+        Ok(())
+    }
+
+    #[test]
+    fn open_auction() {
+        let hands = hands_to_option(PlayerMap::new(
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+        ));
+        let game = Bidding::new(Player::A, hands);
+        let (game, _) = game.bid(250).left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.bid(275).left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        assert_eq!(game.turn(), Player::A);
+        let (game, _) = game.bid(300).left().unwrap();
+        // B passed, so they're skipped
+        assert_eq!(game.turn(), Player::C);
+        let (game, _) = game.bid(325).left().unwrap();
+        assert_eq!(game.turn(), Player::A);
+        let game = game.pass().right().unwrap();
+        assert_eq!(game.turn(), Player::C);
+        assert_eq!(game.winning_bid(), Some((Player::C, 325)));
+    }
+
+    #[test]
+    fn dealer_stuck() {
+        let hands = hands_to_option(PlayerMap::new(
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+            vec![HX, HX, HX, HX],
+        ));
+        let game = Bidding::new(Player::A, hands);
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, err) = game.pass().left().unwrap();
+        assert!(err.is_some());
+        assert_eq!(game.turn(), Player::D);
+        let game = game.bid(250).right().unwrap();
+        assert_eq!(game.turn(), Player::D);
+    }
+
+    #[test]
+    fn deals_from_the_dealer() {
+        let first = PlayerMap::new(vec![HX], vec![QS], vec![JD], vec![AH]);
+        let second = PlayerMap::new(vec![C9], vec![AH], vec![QS], vec![JD]);
+        let rules = RuleSet {
+            dealer_stuck: false,
+            ..RuleSet::default()
+        };
+        let dealer = Dealer::Scripted(vec![first.clone().into(), second.clone().into()]);
+
+        let game = Bidding::with_dealer(Player::A, dealer.clone(), rules);
+        assert_eq!(game.hands, hands_to_option(first));
+        assert_eq!(game.dealer(), &dealer);
+        assert_eq!(game.dealer_seat(), Player::D);
+        assert_eq!(game.initial_bidder(), Player::A);
+        assert_eq!(game.project(Player::A).dealer(), &Dealer::Hidden);
+
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        assert_eq!(game.deals(), 1);
+        assert_eq!(game.hands, hands_to_option(second));
+        // the deal passes to the left
+        assert_eq!(game.dealer_seat(), Player::A);
+        assert_eq!(game.turn(), Player::B);
+    }
+
+    #[test]
+    fn redeals() -> Result<(), GameError> {
+        let first = PlayerMap::new(vec![C9; 5], vec![HX], vec![AH], vec![JD]);
+        let second = PlayerMap::new(vec![AH], vec![HX], vec![AH], vec![QS]);
+        let dealer = Dealer::Scripted(vec![first.into(), second.clone().into()]);
+        let game = Bidding::with_dealer(Player::A, dealer, RuleSet::default());
+
+        // five nines, and the same dealer deals again
+        let game = game.redeal()?;
+        assert_eq!(
+            game.misdeal(),
+            Some(&Misdeal {
+                player: Player::A,
+                hand: vec![C9; 5],
+            })
+        );
+        assert_eq!(game.hands, hands_to_option(second));
+        assert_eq!(game.dealer_seat(), Player::D);
+        assert_eq!(game.turn(), Player::A);
+
+        assert_eq!(game.check_redeal(), Err(GameError::NotAMisdeal));
+        let (game, _) = game.bid(250).left().unwrap();
+        // no meld and no aces
+        assert_eq!(game.check_redeal(), Ok(()));
+        let (game, _) = game.bid(275).left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        assert_eq!(game.turn(), Player::A);
+        assert_eq!(game.clone().redeal(), Err(GameError::AlreadyBid));
+
+        Ok(())
+    }
+
+    #[test]
+    fn projections() {
+        let hands = PlayerMap::new(vec![HX], vec![QS], vec![JD], vec![AH]);
+        let rules = RuleSet {
+            widow: 1,
+            ..RuleSet::default()
+        };
+        let dealer = Dealer::Scripted(vec![dealer::Deal {
+            hands: hands.clone(),
+            widow: vec![C9],
+        }]);
+        let game = Bidding::with_dealer(Player::A, dealer, rules);
+
+        let spectator = game.project_for(Viewer::Spectator);
+        for player in Player::iter().take(4) {
+            assert_eq!(spectator.hand(player), &vec![None]);
+        }
+        assert_eq!(spectator.widow(), &vec![None]);
+        assert_eq!(spectator.dealer(), &Dealer::Hidden);
+        assert_eq!(spectator.bids(), game.bids());
+
+        assert_eq!(
+            game.project_for(Viewer::Player(Player::B)),
+            game.project(Player::B)
+        );
+    }
+
+    #[test]
+    fn double_deck() {
+        let rules = RuleSet::for_deck(Deck::Double);
+        let game = Bidding::with_dealer(Player::A, Dealer::Seeded(1), rules);
+        for (_, hand) in game.hands.iter() {
+            assert_eq!(hand.len(), 20);
+        }
+
+        let (game, err) = game.bid(490).left().unwrap();
+        assert!(err.is_some());
+        let (game, err) = game.bid(510).left().unwrap();
+        assert!(err.is_none());
+        assert_eq!(game.bids(), &[(Player::A, Some(Bid::Points(510)))]);
+    }
+
+    #[test]
+    fn cutthroat() -> Result<(), GameError> {
+        let deal = dealer::Deal {
+            hands: PlayerMap::from_fn(Layout::CUTTHROAT, |p| match p {
+                Player::A => vec![AH, AH],
+                Player::B => vec![C9, C9],
+                _ => vec![QS, JD],
+            }),
+            widow: vec![HX, HX, QS],
+        };
+        let rules = RuleSet::cutthroat();
+        let game = Bidding::with_dealer(Player::A, Dealer::Scripted(vec![deal]), rules);
+        assert_eq!(game.widow, vec![Some(HX), Some(HX), Some(QS)]);
+
+        let (game, _) = game.bid(250).left().unwrap();
+        let (game, _) = game.pass().left().unwrap();
+        let game = game.pass().right().unwrap();
+        assert_eq!(game.turn(), Player::A);
+        assert_eq!(game.hand(Player::A).len(), 5);
+
+        let game = game.select(Suit::Heart).right().unwrap();
+        assert!(game.clone().discard(Some(vec![HX, HX])).is_err());
+        let game = game.discard(Some(vec![HX, HX, QS]))?;
+        assert_eq!(game.project(Player::B).widow, vec![None, None, None]);
+        assert_eq!(game.meld(Player::C), &[Meld::Pinochle(1)]);
+
+        let mut game = game.next();
+        for card in &[AH, C9, QS, AH, C9] {
+            game = game.play(*card).left().unwrap().0;
+        }
+        let game = game.play(JD).right().unwrap();
+
+        // the discards count for the bidder, but don't make the bid
+        assert_eq!(game.taken(Team::Red).len(), 9);
+        assert_eq!(game.counters(Team::Red), 50);
+        assert_eq!(game.round_score(Team::Red), -250);
+        assert_eq!(game.round_score(Team::Blue), 0);
+        assert_eq!(game.round_score(Team::Green), 40);
+
+        Ok(())
+    }
+
+    #[test]
+    fn two_handed() -> Result<(), GameError> {
+        let card = |s: &str| s.parse::<Card>().unwrap();
+        let (kh, qh, as_, h9) = (card("KH"), card("QH"), card("AS"), card("9H"));
+        let deal = dealer::Deal {
+            hands: PlayerMap::from_fn(Layout::HEADS_UP, |p| match p {
+                Player::A => vec![as_, kh, qh],
+                _ => vec![C9, JD, QS],
+            }),
+            widow: vec![C9, AH, kh, h9],
+        };
+        let game = Stock::with_dealer(
+            Player::A,
+            Dealer::Scripted(vec![deal]),
+            RuleSet::two_handed(),
+        );
+        assert_eq!(game.trump(), Suit::Heart);
+        assert_eq!(game.stock_size(), 4);
+        assert_eq!(game.meld(Player::B), &[Meld::Dix]);
+
+        let game = game.play(as_).left().unwrap().0;
+        let game = game.play(C9).right().unwrap();
+        assert_eq!(game.turn(), Player::A);
+        assert_eq!(game.declarations(), vec![Meld::RoyalMarriage]);
+        assert!(game.clone().declare(Some(Meld::Pinochle(1))).is_err());
+
+        let game = game.declare(Some(Meld::RoyalMarriage))?.left().unwrap();
+        assert_eq!(game.shown(Player::A).len(), 2);
+        assert_eq!(game.hand(Player::A), &[Some(kh), Some(qh), Some(kh)]);
+        assert_eq!(game.hand(Player::B), &[Some(JD), Some(QS), Some(AH)]);
+        assert_eq!(game.project(Player::B).widow, vec![None]);
+
+        // a melded card can be led, and trump takes the trick
+        let game = game.play(qh).left().unwrap().0;
+        assert_eq!(game.shown(Player::A).len(), 1);
+        let game = game.play(AH).right().unwrap();
+        assert_eq!(game.turn(), Player::B);
+        assert_eq!(game.taken(Team::Blue), vec![qh, AH]);
+
+        // the loser of the last draw takes the trump card
+        let game = game.declare(Some(Meld::Pinochle(1)))?.right().unwrap();
+        assert_eq!(game.turn(), Player::B);
+        assert_eq!(game.meld(Player::A), &[Meld::RoyalMarriage]);
+        assert_eq!(game.meld(Player::B), &[Meld::Dix, Meld::Pinochle(1)]);
+        assert_eq!(game.hand(Player::A), &[Some(kh), Some(kh), Some(h9)]);
+        assert_eq!(game.hand(Player::B), &[Some(JD), Some(QS), Some(C9)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn declares_only_meld() -> Result<(), GameError> {
+        let cards = |s: &str| notation::parse_cards(s).unwrap();
+        let deal = dealer::Deal {
+            hands: PlayerMap::from_fn(Layout::HEADS_UP, |p| match p {
+                Player::A => cards("AS TS TD TC TH"),
+                _ => cards("9C 9D 9S JC JS"),
+            }),
+            widow: cards("QC AH KH 9H"),
+        };
+        let game = Stock::with_dealer(
+            Player::A,
+            Dealer::Scripted(vec![deal]),
+            RuleSet::two_handed(),
+        );
+        let game = game.play(cards("AS")[0]).left().unwrap().0;
+        let game = game.play(cards("9C")[0]).right().unwrap();
+        assert_eq!(game.turn(), Player::A);
+        assert_eq!(game.declarations(), vec![]);
+
+        // four tens are in hand, but they aren't a meld
+        let tens = Meld::Around(Rank::Ten, 1);
+        assert_eq!(
+            game.clone().declare(Some(tens)).err(),
+            Some(GameError::NotAMeld(tens))
+        );
+        assert!(game.declare(None).is_ok());
+
+        Ok(())
+    }
 }
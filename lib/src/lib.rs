@@ -1,4 +1,5 @@
 pub use self::game::core::*;
 
+pub mod bot;
 pub mod command;
 pub mod game;
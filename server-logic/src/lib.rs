@@ -6,8 +6,15 @@ use futures::{
 };
 use pinochle_lib::{
     command::{Command, PlayingInput, PlayingResponse, TableCommand, TableState},
-    game::{self, states::Project, Game},
-    shuffle, Player, PlayerMap,
+    game::{
+        dealer::Dealer,
+        error::GameError,
+        record::GameRecord,
+        rules::RuleSet,
+        states::{Project, Viewer},
+        Game,
+    },
+    Player, PlayerMap,
 };
 pub use room::*;
 use serde_json::{from_str, to_string};
@@ -28,30 +35,27 @@ where
 
         if let Ok(Some(message)) = message {
             if let Ok(message) = message.to_str() {
-                match from_str(message) {
-                    Ok(Command::JoinTable(name)) => {
-                        let table = state.read().unwrap().get(&name).map(|t| t.clone());
-                        let table = match table {
-                            None => state
-                                .write()
-                                .unwrap()
-                                .entry(name)
-                                .or_insert_with(|| Arc::new(Table::new()))
-                                .clone(),
-                            Some(table) => table,
-                        };
-
-                        match table.join(addr, stream).await {
-                            (result_stream, Ok(())) => {
-                                stream = result_stream;
-                            }
-                            (_, Err(e)) => {
-                                println!("Error: {:?}", e);
-                                break;
-                            }
+                if let Ok(Command::JoinTable(name)) = from_str(message) {
+                    let table = state.read().unwrap().get(&name).cloned();
+                    let table = match table {
+                        None => state
+                            .write()
+                            .unwrap()
+                            .entry(name)
+                            .or_insert_with(|| Arc::new(Table::new()))
+                            .clone(),
+                        Some(table) => table,
+                    };
+
+                    match table.join(addr, stream).await {
+                        (result_stream, Ok(())) => {
+                            stream = result_stream;
+                        }
+                        (_, Err(e)) => {
+                            println!("Error: {:?}", e);
+                            break;
                         }
                     }
-                    _ => {}
                 }
             }
         }
@@ -65,7 +69,7 @@ where
 {
     players: PlayerMap<Option<T>>,
     ready: HashMap<T, bool>,
-    game: Game,
+    game: GameRecord,
 }
 
 enum TableStates<T>
@@ -73,7 +77,7 @@ where
     T: std::hash::Hash + Eq + Copy,
 {
     Lobby(Mutex<TableStateInternal<T>>),
-    Playing(PlayerMap<T>, RwLock<Game>),
+    Playing(PlayerMap<T>, RwLock<GameRecord>),
 }
 
 use TableStates::*;
@@ -103,7 +107,7 @@ where
         TableStateInternal {
             ready: HashMap::new(),
             players: PlayerMap::new(None, None, None, None),
-            game: Game::new(Player::A, shuffle()).into(),
+            game: GameRecord::new(Player::A, Dealer::random(), RuleSet::default()),
         }
     }
 }
@@ -124,12 +128,10 @@ where
         addr: &T,
         message: &str,
         player_map: &PlayerMap<T>,
-        game: &RwLock<Game>,
-    ) -> Result<(Option<TableStates<T>>, Completion), String> {
-        let connected_player = player_map
-            .get_player(addr)
-            .ok_or("Not playing".to_owned())?;
-        let input = from_str(message).map_err(|_| "Invalid message".to_owned())?;
+        game: &RwLock<GameRecord>,
+    ) -> Result<(Option<TableStates<T>>, Completion), GameError> {
+        let connected_player = player_map.get_player(addr).ok_or(GameError::NotSeated)?;
+        let input = from_str(message).map_err(|e| GameError::InvalidMessage(e.to_string()))?;
 
         match input {
             PlayingInput::Resign => {
@@ -138,47 +140,73 @@ where
                 let message = Message::text(message);
                 self.room.broadcast(Signal::Transmit(message));
 
-                Ok((Some(Lobby(Mutex::new(TableStateInternal::new()))), Finished))
+                // the next game is at the same table, with the same house rules
+                let rules = game.read().unwrap().game().rules().clone();
+                let lobby = TableStateInternal {
+                    players: player_map.map(|_, addr| Some(*addr)),
+                    ready: player_map.iter().map(|(_, addr)| (*addr, false)).collect(),
+                    game: GameRecord::new(Player::A, Dealer::random(), rules),
+                };
+
+                Ok((Some(Lobby(Mutex::new(lobby))), Finished))
             }
-            PlayingInput::Play(game_input) => {
+            PlayingInput::Undo => {
                 {
-                    let mut game = game.write().unwrap();
-                    game.play(connected_player, game_input.clone())?
+                    let mut record = game.write().unwrap();
+                    // one player can't take back what another has played
+                    match record.inputs().last() {
+                        Some((player, _)) if *player != connected_player => {
+                            return Err(GameError::NotYourInput)
+                        }
+                        _ => record.undo(1)?,
+                    }
                 }
+                self.send_full_state(game.read().unwrap().game(), player_map);
 
-                match game_input {
-                    game::Input::Next => {
-                        self.send_full_state(&game.read().unwrap(), player_map);
+                Ok((None, Continue))
+            }
+            PlayingInput::Play(game_input) => {
+                // clients can't replay the start of a new phase or a new deal
+                // from the masked input alone (e.g. the meld of hands they can't
+                // see), so send everyone their view of the new state instead
+                let (phase_changed, layout) = {
+                    let mut record = game.write().unwrap();
+                    let phase = (std::mem::discriminant(record.game()), record.game().deals());
+                    record.play(connected_player, game_input.clone())?;
+                    let game = record.game();
+                    (
+                        phase != (std::mem::discriminant(game), game.deals()),
+                        game.rules().layout,
+                    )
+                };
 
-                        Ok((None, Continue))
-                    }
-                    _ => {
-                        self.room.send(|recipient| {
-                            if let Some(recipient) = player_map.get_player(recipient) {
-                                let input = if recipient.team() == connected_player.team() {
-                                    game_input.clone()
-                                } else {
-                                    game_input.mask()
-                                };
-
-                                let response = PlayingResponse::Played(connected_player, input);
-                                let message = to_string(&response).unwrap();
-                                let message = Message::text(message);
-                                Some(Signal::Transmit(message))
-                            } else {
-                                None
-                            }
-                        });
+                if phase_changed {
+                    self.send_full_state(game.read().unwrap().game(), player_map);
+                } else {
+                    self.room.send(|recipient| {
+                        let same_team = player_map
+                            .get_player(recipient)
+                            .is_some_and(|r| layout.team(r) == layout.team(connected_player));
+                        let input = if same_team {
+                            game_input.clone()
+                        } else {
+                            game_input.mask()
+                        };
 
-                        Ok((None, Continue))
-                    }
+                        let response = PlayingResponse::Played(connected_player, input);
+                        let message = to_string(&response).unwrap();
+                        let message = Message::text(message);
+                        Some(Signal::Transmit(message))
+                    });
                 }
+
+                Ok((None, Continue))
             }
         }
     }
 
     fn table_info(&self, player: Option<Player>, s: &TableStateInternal<T>) -> Message {
-        let mut response = TableState::new(player);
+        let mut response = TableState::new(player, s.game.game().rules().clone());
         for (player, ready) in s.ready.iter() {
             if let Some(player) = s.players.get_player(&Some(*player)) {
                 *response.ready.get_value_mut(player) = *ready;
@@ -192,13 +220,39 @@ where
             Lobby(s) => {
                 let mut s = s.lock().unwrap();
 
-                match from_str(&message) {
-                    Ok(TableCommand::SetReady(b)) => {
-                        if s.players.get_player(&Some(*addr)).is_some() {
-                            s.ready.insert(*addr, b);
+                match from_str(message) {
+                    Ok(TableCommand::SetReady(b))
+                        if s.players.get_player(&Some(*addr)).is_some() =>
+                    {
+                        s.ready.insert(*addr, b);
+                    }
+                    // the rules come from a client, so check them before they can
+                    // panic mid-game with the table locked
+                    Ok(TableCommand::SetRules(rules))
+                        if s.players.get_player(&Some(*addr)).is_some()
+                            && rules.validate().is_ok() =>
+                    {
+                        // players left without a seat at the new table have to sit again
+                        let players = PlayerMap::from_fn(rules.layout, |p| {
+                            if s.players.has_player(p) {
+                                *s.players.get_value(p)
+                            } else {
+                                None
+                            }
+                        });
+                        let unseated: Vec<T> = s
+                            .players
+                            .iter()
+                            .filter_map(|(_, addr)| *addr)
+                            .filter(|addr| players.get_player(&Some(*addr)).is_none())
+                            .collect();
+                        for addr in unseated {
+                            s.ready.insert(addr, false);
                         }
+                        s.players = players;
+                        s.game = GameRecord::new(Player::A, Dealer::random(), rules);
                     }
-                    Ok(TableCommand::SetPlayer(p)) => {
+                    Ok(TableCommand::SetPlayer(p)) if s.players.has_player(p) => {
                         if let Some(player) = s.players.get_player(&Some(*addr)) {
                             *s.players.get_value_mut(player) = None;
                         }
@@ -221,13 +275,16 @@ where
 
                 if s.players
                     .iter_all()
-                    .map(|(_, a)| *a.and_then(|a| s.ready.get(&a)).unwrap_or(&false))
-                    .all(|b| b)
+                    .all(|(_, a)| *a.and_then(|a| s.ready.get(&a)).unwrap_or(&false))
                 {
-                    println!("Starting playing");
+                    // the seed is enough to reproduce the game from its inputs
+                    println!(
+                        "Starting playing with seed {:?}",
+                        s.game.game().dealer().seed()
+                    );
 
                     let map = s.players.clone().unwrap();
-                    self.send_full_state(&s.game, &map);
+                    self.send_full_state(s.game.game(), &map);
 
                     let s = Playing(map, RwLock::new(s.game.clone()));
 
@@ -236,7 +293,7 @@ where
                     (None, Continue)
                 }
             }
-            Playing(player_map, game) => match self.play(addr, &message, player_map, game) {
+            Playing(player_map, game) => match self.play(addr, message, player_map, game) {
                 Ok(c) => c,
                 Err(e) => {
                     let response = PlayingResponse::Error(e);
@@ -255,16 +312,20 @@ where
     }
 
     fn send_full_state(&self, game: &Game, players: &PlayerMap<T>) {
-        self.room.send(|dest| {
-            if let Some(player) = players.get_player(dest) {
-                let projected = game.project(player);
-                Some(Signal::Transmit(Message::text(
-                    to_string(&PlayingResponse::State(projected)).unwrap(),
-                )))
-            } else {
-                None
-            }
-        });
+        self.room
+            .send(|dest| Some(Self::state_message(game, players, dest)));
+    }
+
+    /// The game as `dest` can see it, whether they're playing or watching.
+    /// Once a hand is over everyone can go over every card of it.
+    fn state_message(game: &Game, players: &PlayerMap<T>, dest: &T) -> Signal<T> {
+        let viewer = players
+            .get_player(dest)
+            .map_or(Viewer::Spectator, Viewer::Player);
+        let projected = game.review().unwrap_or_else(|_| game.project_for(viewer));
+        Signal::Transmit(Message::text(
+            to_string(&PlayingResponse::State(Box::new(projected))).unwrap(),
+        ))
     }
 
     fn main_loop<E>(
@@ -310,20 +371,29 @@ where
             .enter(
                 a,
                 stream,
-                || match &*self.state.read().unwrap() {
-                    Lobby(table_state) => {
-                        let mut table_state = table_state.lock().unwrap();
+                || {
+                    match &*self.state.read().unwrap() {
+                        Lobby(table_state) => {
+                            let mut table_state = table_state.lock().unwrap();
 
-                        let player = table_state.players.get_player(&None);
+                            let player = table_state.players.get_player(&None);
 
-                        if let Some(player) = player {
-                            *table_state.players.get_value_mut(player) = Some(a);
-                        }
+                            if let Some(player) = player {
+                                *table_state.players.get_value_mut(player) = Some(a);
+                            }
 
-                        self.room
-                            .send_to(&a, Signal::Transmit(self.table_info(player, &table_state)));
+                            self.room.send_to(
+                                &a,
+                                Signal::Transmit(self.table_info(player, &table_state)),
+                            );
+                        }
+                        // anyone arriving mid-game watches
+                        Playing(player_map, game) => {
+                            let game = game.read().unwrap();
+                            self.room
+                                .send_to(&a, Self::state_message(game.game(), player_map, &a));
+                        }
                     }
-                    _ => {}
                 },
                 |out, m| self.main_loop(&a, out, m, &mut result),
             )
@@ -381,3 +451,81 @@ where
         (stream, result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinochle_lib::{game::Input, Layout};
+
+    #[test]
+    fn only_the_last_player_can_undo() -> Result<(), GameError> {
+        let table = Table::new();
+        let players = PlayerMap::new(0, 1, 2, 3);
+        let game = RwLock::new(GameRecord::new(
+            Player::A,
+            Dealer::Seeded(7),
+            RuleSet::default(),
+        ));
+        game.write().unwrap().play(Player::A, Input::Bid(250))?;
+        let undo = to_string(&PlayingInput::Undo).unwrap();
+
+        let refused = table.play(&1, &undo, &players, &game);
+        assert_eq!(refused.err(), Some(GameError::NotYourInput));
+        assert_eq!(game.read().unwrap().inputs().len(), 1);
+
+        assert!(table.play(&0, &undo, &players, &game).is_ok());
+        assert!(game.read().unwrap().inputs().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn turns_down_bad_messages() {
+        let table = Table::new();
+        let players = PlayerMap::new(0, 1, 2, 3);
+        let game = RwLock::new(GameRecord::new(
+            Player::A,
+            Dealer::Seeded(7),
+            RuleSet::default(),
+        ));
+        let bid = to_string(&PlayingInput::Play(Input::Bid(250))).unwrap();
+
+        let spectator = table.play(&4, &bid, &players, &game);
+        assert_eq!(spectator.err(), Some(GameError::NotSeated));
+        let garbled = table.play(&0, "{", &players, &game);
+        assert!(matches!(garbled.err(), Some(GameError::InvalidMessage(_))));
+        assert!(game.read().unwrap().inputs().is_empty());
+    }
+
+    #[test]
+    fn table_info_has_the_rules() {
+        let table = Table::<usize>::new();
+        let mut lobby = TableStateInternal::new();
+        lobby.game = GameRecord::new(Player::A, Dealer::Seeded(7), RuleSet::cutthroat());
+
+        let message = table.table_info(Some(Player::B), &lobby);
+        let state: TableState = from_str(message.to_str().unwrap()).unwrap();
+        assert_eq!(state.rules, RuleSet::cutthroat());
+        assert_eq!(state.player, Some(Player::B));
+        assert_eq!(state.ready.iter().count(), 3);
+    }
+
+    #[test]
+    fn resigning_keeps_the_table() {
+        let table = Table::new();
+        let players = PlayerMap::from_fn(Layout::CUTTHROAT, |p| p as usize);
+        let rules = RuleSet::cutthroat();
+        let game = RwLock::new(GameRecord::new(Player::A, Dealer::Seeded(7), rules.clone()));
+        let resign = to_string(&PlayingInput::Resign).unwrap();
+
+        match table.play(&2, &resign, &players, &game) {
+            Ok((Some(Lobby(lobby)), Finished)) => {
+                let lobby = lobby.into_inner().unwrap();
+                assert_eq!(lobby.game.game().rules(), &rules);
+                assert_eq!(lobby.players, players.map(|_, addr| Some(*addr)));
+                assert!(lobby.ready.values().all(|ready| !ready));
+            }
+            _ => panic!("resigning should go back to the lobby"),
+        }
+    }
+}
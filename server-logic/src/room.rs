@@ -19,6 +19,16 @@ pub enum Completion {
 
 pub use Completion::*;
 
+impl<Key, PeerMessage> Default for Room<Key, PeerMessage>
+where
+    Key: Eq + std::hash::Hash + Clone,
+    PeerMessage: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<Key, PeerMessage> Room<Key, PeerMessage>
 where
     Key: Eq + std::hash::Hash + Clone,